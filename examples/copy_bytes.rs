@@ -0,0 +1,24 @@
+use benchmarking::Throughput;
+
+fn main() {
+    const BUFFER_LENGTH: usize = 4096;
+
+    benchmarking::warm_up();
+
+    let bench_result = benchmarking::bench_function(|measurer| {
+        let source = vec![0u8; BUFFER_LENGTH];
+        let mut target = vec![0u8; BUFFER_LENGTH];
+
+        measurer.measure_with_throughput(Throughput::Bytes(BUFFER_LENGTH as u64), || {
+            target.copy_from_slice(&source);
+        });
+
+        target
+    })
+    .unwrap();
+
+    println!(
+        "Copying bytes runs at {:.2} MB/s!",
+        bench_result.bytes_per_second().unwrap() / 1_000_000.0
+    );
+}
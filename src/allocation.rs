@@ -0,0 +1,233 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::black_box;
+
+/// How many times the heap was used, counted by `CountingAllocator`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+    /// Calls to `alloc` and `alloc_zeroed`.
+    pub allocations:       u64,
+    /// Calls to `dealloc`.
+    pub deallocations:     u64,
+    /// Calls to `realloc`.
+    pub reallocations:     u64,
+    /// Bytes requested by allocations, plus the growth of reallocations.
+    pub bytes_allocated:   u64,
+    /// Bytes released by deallocations, plus the shrinkage of reallocations.
+    pub bytes_deallocated: u64,
+}
+
+impl AllocationStats {
+    #[inline]
+    pub(crate) fn add(&mut self, other: &AllocationStats) {
+        self.allocations += other.allocations;
+        self.deallocations += other.deallocations;
+        self.reallocations += other.reallocations;
+        self.bytes_allocated += other.bytes_allocated;
+        self.bytes_deallocated += other.bytes_deallocated;
+    }
+
+    #[inline]
+    pub(crate) fn since(self, earlier: AllocationStats) -> AllocationStats {
+        AllocationStats {
+            allocations:       self.allocations.wrapping_sub(earlier.allocations),
+            deallocations:     self.deallocations.wrapping_sub(earlier.deallocations),
+            reallocations:     self.reallocations.wrapping_sub(earlier.reallocations),
+            bytes_allocated:   self.bytes_allocated.wrapping_sub(earlier.bytes_allocated),
+            bytes_deallocated: self.bytes_deallocated.wrapping_sub(earlier.bytes_deallocated),
+        }
+    }
+}
+
+/// The heap footprint of a closure, measured by `measure_memory`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The highest number of bytes held on the heap at once while the closure was running, above what was held when it started.
+    pub peak_bytes:     u64,
+    /// The number of bytes still held after the closure returned, which are mostly owned by its return value. It is negative if the closure freed more than it allocated.
+    pub retained_bytes: i64,
+    /// How many times the heap was used by the closure.
+    pub allocations:    AllocationStats,
+}
+
+#[derive(Clone, Copy)]
+struct ThreadCounts {
+    stats: AllocationStats,
+    /// The highest value of `bytes_allocated - bytes_deallocated` since the last reset.
+    peak:  i64,
+}
+
+impl ThreadCounts {
+    /// The counts of a new thread. It is a `const fn` so that the thread-local counts are initialized without allocating, which would re-enter the allocator.
+    #[inline]
+    const fn zero() -> ThreadCounts {
+        ThreadCounts {
+            stats: AllocationStats {
+                allocations:       0,
+                deallocations:     0,
+                reallocations:     0,
+                bytes_allocated:   0,
+                bytes_deallocated: 0,
+            },
+            peak:  0,
+        }
+    }
+
+    #[inline]
+    fn live(&self) -> i64 {
+        self.stats.bytes_allocated.wrapping_sub(self.stats.bytes_deallocated) as i64
+    }
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static COUNTS: Cell<ThreadCounts> = const { Cell::new(ThreadCounts::zero()) };
+}
+
+/// A global allocator which wraps another one (the system allocator by default) and counts how the heap is used by every thread. `Measurer` reports the counts of the measured closures once it is installed.
+///
+/// ```rust
+/// use std::alloc::System;
+///
+/// use benchmarking::CountingAllocator;
+///
+/// #[global_allocator]
+/// static GLOBAL: CountingAllocator = CountingAllocator::new(System);
+///
+/// fn main() {
+///     let bench_result = benchmarking::measure_function(|measurer| {
+///         measurer.measure(|| vec![0u8; 64]);
+///     })
+///     .unwrap();
+///
+///     assert_eq!(Some(1.0), bench_result.allocations_per_iteration());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    #[inline]
+    /// Wrap an allocator.
+    pub const fn new(inner: A) -> CountingAllocator<A> {
+        CountingAllocator {
+            inner,
+        }
+    }
+}
+
+#[inline]
+fn count(f: impl FnOnce(&mut AllocationStats)) {
+    // only read the flag after it is set, so that threads do not contend for its cache line
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+
+    // the counts are gone while the thread is being destroyed
+    let _ = COUNTS.try_with(|counts| {
+        let mut thread_counts = counts.get();
+
+        f(&mut thread_counts.stats);
+
+        thread_counts.peak = thread_counts.peak.max(thread_counts.live());
+
+        counts.set(thread_counts);
+    });
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(|stats| {
+            stats.allocations += 1;
+            stats.bytes_allocated += layout.size() as u64;
+        });
+
+        self.inner.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count(|stats| {
+            stats.deallocations += 1;
+            stats.bytes_deallocated += layout.size() as u64;
+        });
+
+        self.inner.dealloc(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(|stats| {
+            stats.allocations += 1;
+            stats.bytes_allocated += layout.size() as u64;
+        });
+
+        self.inner.alloc_zeroed(layout)
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(|stats| {
+            stats.reallocations += 1;
+
+            if new_size > layout.size() {
+                stats.bytes_allocated += (new_size - layout.size()) as u64;
+            } else {
+                stats.bytes_deallocated += (layout.size() - new_size) as u64;
+            }
+        });
+
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Get the counts of the current thread so far, if `CountingAllocator` is the global allocator.
+#[inline]
+pub(crate) fn allocation_stats() -> Option<AllocationStats> {
+    if INSTALLED.load(Ordering::Relaxed) {
+        COUNTS.try_with(|counts| counts.get().stats).ok()
+    } else {
+        None
+    }
+}
+
+/// Run a function once and measure its heap footprint on the current thread: the peak number of bytes held while it runs, and the number of bytes retained by what it returns. It needs `CountingAllocator` to be the global allocator, otherwise `None` is returned.
+pub fn measure_memory<F, O>(f: F) -> Option<MemoryUsage>
+where
+    F: FnOnce() -> O, {
+    let start = COUNTS
+        .try_with(|counts| {
+            let mut thread_counts = counts.get();
+
+            thread_counts.peak = thread_counts.live();
+
+            counts.set(thread_counts);
+
+            thread_counts
+        })
+        .ok()?;
+
+    let output = black_box(f());
+
+    let end = COUNTS.try_with(Cell::get).ok()?;
+
+    drop(output);
+
+    if !INSTALLED.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    Some(MemoryUsage {
+        peak_bytes:     (end.peak - start.live()) as u64,
+        retained_bytes: end.live() - start.live(),
+        allocations:    end.stats.since(start.stats),
+    })
+}
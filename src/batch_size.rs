@@ -0,0 +1,24 @@
+/// How many inputs are prepared and measured together by `Measurer::measure_batched`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchSize {
+    /// One input per batch. The timer is read for every iteration, so it suits functions which take much longer than reading the clock.
+    PerIteration,
+    /// `100` inputs per batch, for inputs which are cheap to hold in memory.
+    SmallInput,
+    /// `10` inputs per batch, for inputs which take a lot of memory.
+    LargeInput,
+    /// A specific number of inputs per batch. `0` is regarded as `1`.
+    NumIterations(u64),
+}
+
+impl BatchSize {
+    #[inline]
+    pub(crate) fn iterations(self) -> u64 {
+        match self {
+            BatchSize::PerIteration => 1,
+            BatchSize::SmallInput => 100,
+            BatchSize::LargeInput => 10,
+            BatchSize::NumIterations(iterations) => iterations.max(1),
+        }
+    }
+}
@@ -0,0 +1,676 @@
+use std::{
+    panic,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    black_box, resource_usage::ResourceUsageStart, warm_up_multi_thread_with_duration,
+    warm_up_with_duration, BenchmarkError, Clock, MeasureResult, Measurer, Precision,
+    ResourceUsage, DEFAULT_MEASURE_DURATION, DEFAULT_MEASURE_TIMES,
+};
+
+/// A benchmark to run a function repeatly and measure its execution time.
+///
+/// The function is run until it has been run `times` times or `duration` has elapsed, whichever comes first. If `target_time` is set, the number of runs is chosen from how long a run takes. If `precision` is set, it also stops once the results are precise enough (but not before `min_times` runs and `min_duration`). If none of them is set, it is run `10` times.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use benchmarking::Bench;
+///
+/// let bench_result = Bench::new()
+///     .duration(Duration::from_millis(100))
+///     .measurers(2)
+///     .run(|measurers| {
+///         let mut vec: Vec<usize> = Vec::with_capacity(100);
+///
+///         measurers[0].measure(|| {
+///             for i in 0..100 {
+///                 vec.push(i);
+///             }
+///         });
+///
+///         measurers[1].measure(|| vec.iter().sum::<usize>());
+///     })
+///     .unwrap();
+///
+/// println!("Filling a vec takes {:?}!", bench_result.results()[0].elapsed());
+/// println!("Summing a vec takes {:?}!", bench_result.results()[1].elapsed());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Bench {
+    times:        Option<u64>,
+    duration:     Option<Duration>,
+    /// The wall time to calibrate the number of runs to.
+    target_time:  Option<Duration>,
+    precision:    Option<Precision>,
+    min_times:    u64,
+    min_duration: Duration,
+    threads:      usize,
+    measurers:    usize,
+    warm_up:      Option<Duration>,
+    /// `None` means the default clock of `Measurer`.
+    clock:        Option<&'static dyn Clock>,
+}
+
+/// The results of a `Bench`, one for each measurer.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    results:           Vec<MeasureResult>,
+    precision_reached: Option<bool>,
+    /// The resource usage of every thread, starting with the current thread.
+    resource_usages:   Vec<ResourceUsage>,
+}
+
+impl BenchResult {
+    #[inline]
+    /// Get the result of the first measurer.
+    pub fn result(&self) -> &MeasureResult {
+        &self.results[0]
+    }
+
+    #[inline]
+    /// Get the results of all measurers.
+    pub fn results(&self) -> &[MeasureResult] {
+        &self.results
+    }
+
+    #[inline]
+    /// Check whether the results of all measurers reached the target precision of the benchmark. It returns `None` if no target was set.
+    pub fn is_precision_reached(&self) -> Option<bool> {
+        self.precision_reached
+    }
+
+    #[inline]
+    /// Get the `getrusage` usage of the run on every thread, starting with the current thread (the other threads are in the order they finished). Unlike `MeasureResult::resource_usage`, it is also available if there is more than one measurer. It is empty if the usage is not available (see `MeasureResult::resource_usage`).
+    pub fn resource_usages(&self) -> &[ResourceUsage] {
+        &self.resource_usages
+    }
+
+    /// Get the `getrusage` usage of the run summed over all threads. It returns `None` if the usage is not available.
+    pub fn resource_usage(&self) -> Option<ResourceUsage> {
+        let (first, others) = self.resource_usages.split_first()?;
+
+        let mut total = *first;
+
+        for usage in others {
+            total.add(usage);
+        }
+
+        Some(total)
+    }
+
+    #[inline]
+    /// Take the result of the first measurer.
+    pub fn into_result(self) -> MeasureResult {
+        self.results.into_iter().next().unwrap()
+    }
+
+    #[inline]
+    /// Take the results of all measurers.
+    pub fn into_results(self) -> Vec<MeasureResult> {
+        self.results
+    }
+}
+
+impl Default for Bench {
+    #[inline]
+    fn default() -> Self {
+        Bench::new()
+    }
+}
+
+impl Bench {
+    #[inline]
+    /// Create a benchmark which runs a function `10` times with one measurer on one thread.
+    pub fn new() -> Bench {
+        Bench {
+            times:        None,
+            duration:     None,
+            target_time:  None,
+            precision:    None,
+            min_times:    DEFAULT_MEASURE_TIMES,
+            min_duration: Duration::from_secs(0),
+            threads:      1,
+            measurers:    1,
+            warm_up:      None,
+            clock:        None,
+        }
+    }
+
+    #[inline]
+    /// Set the maximum number of runs (on each thread). The function is run at least once.
+    pub fn times(mut self, times: u64) -> Bench {
+        self.times = Some(times.max(1));
+
+        self
+    }
+
+    #[inline]
+    /// Set the maximum duration to run. The function is run at least twice.
+    pub fn duration(mut self, duration: Duration) -> Bench {
+        self.duration = Some(duration);
+
+        self
+    }
+
+    #[inline]
+    /// Calibrate the number of runs so that the whole benchmark takes about a specific wall time. The second run is timed as a probe (the first one is likely to be slowed down by cold caches and lazy initialization), and the function is run `target_time / probe` times in total (at least twice, and no more than `times` if it is set).
+    pub fn target_time(mut self, target_time: Duration) -> Bench {
+        self.target_time = Some(target_time);
+
+        self
+    }
+
+    #[inline]
+    /// Stop as soon as the mean iteration time of every measurer is precise enough. `times` and `duration` still bound the benchmark; if neither of them is set, it runs for at most `5` seconds. Use `BenchResult::is_precision_reached` to check whether the target was reached.
+    pub fn precision(mut self, precision: Precision) -> Bench {
+        self.precision = Some(precision);
+
+        self
+    }
+
+    #[inline]
+    /// Set the minimum number of runs (on each thread) before stopping for precision. The default value is `10`, so that the spread of iteration times can be estimated.
+    pub fn min_times(mut self, min_times: u64) -> Bench {
+        self.min_times = min_times;
+
+        self
+    }
+
+    #[inline]
+    /// Set the minimum duration to run before stopping for precision.
+    pub fn min_duration(mut self, min_duration: Duration) -> Bench {
+        self.min_duration = min_duration;
+
+        self
+    }
+
+    #[inline]
+    /// Set the number of threads used by `run_multi_thread`. The results of all threads are merged, and their total elapsed time is divided by the number of threads, so `MeasureResult::elapsed` and `MeasureResult::speed` reflect the throughput of all threads together. The other iteration time statistics (such as `min`, `std_dev`, the percentiles and `MeasureResult::thread_elapsed`) are those of a single thread. There is at least one thread.
+    pub fn threads(mut self, threads: usize) -> Bench {
+        self.threads = threads.max(1);
+
+        self
+    }
+
+    #[inline]
+    /// Set the number of measurers passed to the function. There is at least one measurer.
+    pub fn measurers(mut self, measurers: usize) -> Bench {
+        self.measurers = measurers.max(1);
+
+        self
+    }
+
+    #[inline]
+    /// Set the clock used by the measurers to time iterations (see `Measurer::set_clock`). The durations of the benchmark itself are always wall-clock time.
+    pub fn clock(mut self, clock: &'static dyn Clock) -> Bench {
+        self.clock = Some(clock);
+
+        self
+    }
+
+    #[inline]
+    /// Stimulate the CPUs (as many as the threads) to wake up for a specific duration before running.
+    pub fn warm_up(mut self, duration: Duration) -> Bench {
+        self.warm_up = Some(duration);
+
+        self
+    }
+
+    /// Run a function on the current thread and measure its execution time. It always uses only the current thread and ignores the number of threads; use `run_multi_thread` for more.
+    pub fn run<F, O>(&self, f: F) -> Result<BenchResult, BenchmarkError>
+    where
+        F: FnMut(&mut [Measurer]) -> O, {
+        self.warm_up_cpus(1);
+
+        let (results, resource_usage) =
+            self.run_with_resource_usage(ResourceUsageStart::current(), f)?;
+
+        Ok(self.finish(results, resource_usage.into_iter().collect()))
+    }
+
+    /// Run a function with a number of threads and measure its execution time. If the function fails on any thread (such as a measurer which has not measured), the error is returned; if it panics on another thread, the panic is resumed on the current thread.
+    pub fn run_multi_thread<F, O>(&self, f: F) -> Result<BenchResult, BenchmarkError>
+    where
+        F: Fn(&mut [Measurer]) -> O + Send + Sync + 'static, {
+        self.warm_up_cpus(self.threads);
+
+        let (tx, rx) = mpsc::channel();
+
+        let f = Arc::new(f);
+
+        let handles: Vec<_> = (1..self.threads)
+            .map(|_| {
+                let tx = tx.clone();
+
+                let f = f.clone();
+
+                let bench = self.clone();
+
+                thread::spawn(move || {
+                    let results = bench
+                        .run_with_resource_usage(ResourceUsageStart::thread(), |measurers| {
+                            f(measurers)
+                        });
+
+                    // the receiver is gone if another thread has failed
+                    let _ = tx.send(results);
+                })
+            })
+            .collect();
+
+        drop(tx);
+
+        let (mut results, resource_usage) =
+            self.run_with_resource_usage(ResourceUsageStart::thread(), |measurers| f(measurers))?;
+
+        let mut resource_usages: Vec<ResourceUsage> = resource_usage.into_iter().collect();
+
+        for _ in 1..self.threads {
+            let (thread_results, thread_resource_usage) = match rx.recv() {
+                Ok(thread_results) => thread_results?,
+                // a thread panicked before sending its results, so raise its panic here
+                Err(_) => {
+                    for handle in handles {
+                        if let Err(panic) = handle.join() {
+                            panic::resume_unwind(panic);
+                        }
+                    }
+
+                    unreachable!()
+                },
+            };
+
+            for (result, thread_result) in results.iter_mut().zip(thread_results) {
+                result.merge(thread_result);
+            }
+
+            resource_usages.extend(thread_resource_usage);
+        }
+
+        for result in results.iter_mut() {
+            result.total_elapsed /= self.threads as u32;
+        }
+
+        Ok(self.finish(results, resource_usages))
+    }
+
+    #[inline]
+    fn finish(
+        &self,
+        results: Vec<MeasureResult>,
+        resource_usages: Vec<ResourceUsage>,
+    ) -> BenchResult {
+        let precision_reached = self.precision.map(|precision| is_precise(precision, &results));
+
+        BenchResult {
+            results,
+            precision_reached,
+            resource_usages,
+        }
+    }
+
+    fn warm_up_cpus(&self, threads: usize) {
+        if let Some(duration) = self.warm_up {
+            if threads > 1 {
+                warm_up_multi_thread_with_duration(threads, duration);
+            } else {
+                warm_up_with_duration(duration);
+            }
+        }
+    }
+
+    /// Run a function on the current thread and get the resource usage of the run since `start`. The resource usage is also attributed to the result when there is only one measurer.
+    fn run_with_resource_usage<F, O>(
+        &self,
+        start: ResourceUsageStart,
+        f: F,
+    ) -> Result<(Vec<MeasureResult>, Option<ResourceUsage>), BenchmarkError>
+    where
+        F: FnMut(&mut [Measurer]) -> O, {
+        let mut results = self.run_on_current_thread(f)?;
+
+        let resource_usage = start.finish();
+
+        if self.measurers == 1 {
+            results[0].resource_usage = resource_usage;
+        }
+
+        Ok((results, resource_usage))
+    }
+
+    fn run_on_current_thread<F, O>(&self, mut f: F) -> Result<Vec<MeasureResult>, BenchmarkError>
+    where
+        F: FnMut(&mut [Measurer]) -> O, {
+        let mut measurers: Vec<Measurer> = (0..self.measurers)
+            .map(|_| {
+                let mut measurer = Measurer::default();
+
+                if let Some(clock) = self.clock {
+                    measurer.set_clock(clock);
+                }
+
+                measurer
+            })
+            .collect();
+
+        black_box(f(&mut measurers));
+
+        let mut times = match (self.times, self.duration, self.target_time, self.precision) {
+            (None, None, None, None) => Some(DEFAULT_MEASURE_TIMES),
+            (times, ..) => times,
+        };
+
+        let duration = match (self.times, self.duration, self.precision) {
+            (None, None, Some(_)) => Some(Duration::from_millis(DEFAULT_MEASURE_DURATION)),
+            (_, duration, _) => duration,
+        };
+
+        let mut results = Vec::with_capacity(self.measurers);
+
+        for measurer in measurers.iter_mut() {
+            results.push(measurer.take_result()?);
+        }
+
+        let mut runs = 1;
+
+        let start = Instant::now();
+
+        loop {
+            if let Some(times) = times {
+                if runs >= times {
+                    break;
+                }
+            }
+
+            let run_start = Instant::now();
+
+            black_box(f(&mut measurers));
+
+            let run_elapsed = run_start.elapsed();
+
+            for (result, measurer) in results.iter_mut().zip(measurers.iter_mut()) {
+                result.merge(measurer.take_result()?);
+
+                measurer.seq += 1;
+            }
+
+            runs += 1;
+
+            if let (2, Some(target_time)) = (runs, self.target_time) {
+                let calibrated = (target_time.as_nanos() / run_elapsed.as_nanos().max(1))
+                    .max(2)
+                    .min(u128::from(u64::MAX)) as u64;
+
+                times = Some(times.map_or(calibrated, |times| times.min(calibrated)));
+            }
+
+            let elapsed = start.elapsed();
+
+            if let Some(duration) = duration {
+                if elapsed >= duration {
+                    break;
+                }
+            }
+
+            if let Some(precision) = self.precision {
+                if runs >= self.min_times
+                    && elapsed >= self.min_duration
+                    && is_precise(precision, &results)
+                {
+                    break;
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+#[inline]
+fn is_precise(precision: Precision, results: &[MeasureResult]) -> bool {
+    results.iter().all(|result| precision.is_reached_by(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn times_gives_exact_runs() {
+        for times in [1, 2, 7] {
+            let mut runs = 0;
+
+            let result = Bench::new()
+                .times(times)
+                .run(|measurers| {
+                    runs += 1;
+
+                    measurers[0].record(Duration::from_nanos(100));
+                })
+                .unwrap()
+                .into_result();
+
+            assert_eq!(times, runs);
+            assert_eq!(u128::from(times), result.times());
+        }
+    }
+
+    #[test]
+    fn duration_gives_at_least_two_runs() {
+        let mut runs = 0;
+
+        let result = Bench::new()
+            .duration(Duration::from_secs(0))
+            .run(|measurers| {
+                runs += 1;
+
+                measurers[0].record(Duration::from_nanos(100));
+            })
+            .unwrap()
+            .into_result();
+
+        assert_eq!(2, runs);
+        assert_eq!(2, result.times());
+    }
+
+    #[test]
+    fn target_time_gives_at_least_two_runs() {
+        let mut runs = 0;
+
+        Bench::new()
+            .target_time(Duration::from_nanos(1))
+            .run(|measurers| {
+                runs += 1;
+
+                measurers[0].record(Duration::from_nanos(100));
+            })
+            .unwrap();
+
+        assert_eq!(2, runs);
+
+        let mut runs = 0;
+
+        Bench::new()
+            .target_time(Duration::from_secs(3600))
+            .times(5)
+            .run(|measurers| {
+                runs += 1;
+
+                measurers[0].record(Duration::from_nanos(100));
+            })
+            .unwrap();
+
+        assert_eq!(5, runs);
+    }
+
+    #[test]
+    fn precision_waits_for_min_times() {
+        for (min_times, expected_runs) in [(None, DEFAULT_MEASURE_TIMES), (Some(5), 5)] {
+            let mut runs = 0;
+
+            let mut bench = Bench::new().precision(Precision::RelativeStandardError(0.5));
+
+            if let Some(min_times) = min_times {
+                bench = bench.min_times(min_times);
+            }
+
+            let bench_result = bench
+                .run(|measurers| {
+                    runs += 1;
+
+                    measurers[0].record(Duration::from_nanos(100));
+                })
+                .unwrap();
+
+            assert_eq!(expected_runs, runs);
+            assert_eq!(Some(true), bench_result.is_precision_reached());
+        }
+    }
+
+    #[test]
+    fn precision_is_not_reached_at_the_cap() {
+        let mut runs = 0;
+
+        let bench_result = Bench::new()
+            .precision(Precision::RelativeStandardError(0.0))
+            .times(20)
+            .run(|measurers| {
+                runs += 1;
+
+                measurers[0].record(Duration::from_nanos(100 * (1 + runs % 2)));
+            })
+            .unwrap();
+
+        assert_eq!(20, runs);
+        assert_eq!(Some(false), bench_result.is_precision_reached());
+    }
+
+    #[test]
+    fn passed_runs_give_empty_results() {
+        for passed_run in [1, 3] {
+            let mut runs = 0;
+
+            let result = Bench::new()
+                .times(4)
+                .run(|measurers| {
+                    runs += 1;
+
+                    measurers[0].set_retain_samples(true);
+
+                    if runs == passed_run {
+                        measurers[0].pass();
+                    } else {
+                        measurers[0].record(Duration::from_nanos(100 * runs));
+                    }
+                })
+                .unwrap()
+                .into_result();
+
+            assert_eq!(4, runs);
+            assert_eq!(3, result.times(), "{}", passed_run);
+            assert_eq!(Some(3), result.samples().map(<[f64]>::len), "{}", passed_run);
+
+            let expected_min = if passed_run == 1 { 200 } else { 100 };
+
+            assert_eq!(Some(Duration::from_nanos(expected_min)), result.min(), "{}", passed_run);
+            assert_eq!(Some(Duration::from_nanos(400)), result.max(), "{}", passed_run);
+        }
+    }
+
+    #[test]
+    fn multi_thread_elapsed_is_divided_by_threads() {
+        let result = Bench::new()
+            .times(4)
+            .threads(2)
+            .run_multi_thread(|measurers| measurers[0].record(Duration::from_nanos(1000)))
+            .unwrap()
+            .into_result();
+
+        assert_eq!(8, result.times());
+        assert_eq!(Duration::from_nanos(500), result.elapsed());
+        assert_eq!(Duration::from_nanos(1000), result.thread_elapsed());
+        assert_eq!(Some(Duration::from_nanos(1000)), result.max());
+        assert_eq!(2.0, result.speed() / result.thread_speed());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn resource_usage_of_every_thread() {
+        let bench_result = Bench::new()
+            .times(2)
+            .threads(3)
+            .measurers(2)
+            .run_multi_thread(|measurers| {
+                measurers[0].record(Duration::from_nanos(1000));
+                measurers[1].record(Duration::from_nanos(1000));
+            })
+            .unwrap();
+
+        assert_eq!(3, bench_result.resource_usages().len());
+        assert!(bench_result.resource_usage().is_some());
+        assert_eq!(None, bench_result.result().resource_usage());
+    }
+
+    #[derive(Debug)]
+    struct CountingClock;
+
+    static CLOCK_TICKS: AtomicU64 = AtomicU64::new(0);
+
+    impl Clock for CountingClock {
+        fn now(&self) -> u64 {
+            CLOCK_TICKS.fetch_add(1, Ordering::Relaxed)
+        }
+
+        fn to_nanos(&self, ticks: u64) -> f64 {
+            ticks as f64 * 1000.0
+        }
+    }
+
+    #[test]
+    fn clock_is_used_by_measurers() {
+        let result = Bench::new()
+            .times(3)
+            .clock(&CountingClock)
+            .run(|measurers| measurers[0].measure(|| ()))
+            .unwrap()
+            .into_result();
+
+        // every reading advances the clock by one tick, which is a microsecond
+        assert_eq!(3, result.times());
+        assert_eq!(Duration::from_micros(1), result.elapsed());
+        assert_eq!(Duration::from_micros(1), result.clock_resolution());
+    }
+
+    #[test]
+    fn multi_thread_error_is_returned() {
+        let main_thread = thread::current().id();
+
+        let result = Bench::new().times(3).threads(3).run_multi_thread(move |measurers| {
+            if thread::current().id() == main_thread {
+                measurers[0].measure(|| ());
+            }
+        });
+
+        assert!(matches!(result, Err(BenchmarkError::MeasurerNotMeasured)));
+    }
+
+    #[test]
+    #[should_panic(expected = "worker")]
+    fn multi_thread_panic_is_resumed() {
+        let main_thread = thread::current().id();
+
+        let _ = Bench::new().times(3).threads(2).run_multi_thread(move |measurers| {
+            if thread::current().id() != main_thread {
+                panic!("worker");
+            }
+
+            measurers[0].measure(|| ());
+        });
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+use crate::{rng::Rng, stats};
+
+/// The options of bootstrap resampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootstrapConfig {
+    /// The confidence level of intervals, between `0.0` and `1.0`. The default value is `0.95`.
+    pub confidence_level: f64,
+    /// How many times to resample. The default value is `10000`.
+    pub resamples:        usize,
+    /// The seed of the pseudorandom number generator. The same seed and samples always produce the same intervals.
+    pub seed:             u64,
+}
+
+impl Default for BootstrapConfig {
+    #[inline]
+    fn default() -> Self {
+        BootstrapConfig {
+            confidence_level: 0.95, resamples: 10000, seed: 0
+        }
+    }
+}
+
+impl BootstrapConfig {
+    #[inline]
+    /// Check whether the confidence level is between `0.0` and `1.0` (exclusive) and there is at least one resample.
+    pub fn is_valid(&self) -> bool {
+        self.resamples > 0 && self.confidence_level > 0.0 && self.confidence_level < 1.0
+    }
+}
+
+/// A point estimate with its confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceInterval {
+    pub estimate:         Duration,
+    pub lower:            Duration,
+    pub upper:            Duration,
+    pub confidence_level: f64,
+}
+
+impl ConfidenceInterval {
+    #[inline]
+    /// Get the half of the width of this interval.
+    pub fn half_width(&self) -> Duration {
+        (self.upper - self.lower) / 2
+    }
+}
+
+impl Display for ConfidenceInterval {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "{:?} ± {:?} ({}% CI)",
+            self.estimate,
+            self.half_width(),
+            self.confidence_level * 100.0
+        ))
+    }
+}
+
+/// The bootstrap confidence intervals of iteration times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bootstrap {
+    pub mean:   ConfidenceInterval,
+    pub median: ConfidenceInterval,
+}
+
+/// Compute the percentile bootstrap confidence intervals of the mean and the median of `samples`, where every sample stands for `weights` iterations. The samples are resampled with their weights. It returns `None` if there is no sample or the config is invalid.
+pub(crate) fn bootstrap(
+    samples: &[f64],
+    weights: &[u64],
+    config: &BootstrapConfig,
+) -> Option<Bootstrap> {
+    if samples.is_empty() || !config.is_valid() {
+        return None;
+    }
+
+    let mut rng = Rng::new(config.seed);
+
+    let mut means = Vec::with_capacity(config.resamples);
+    let mut medians = Vec::with_capacity(config.resamples);
+
+    if weights.iter().all(|weight| *weight == 1) {
+        let mut resample = vec![0.0; samples.len()];
+
+        for _ in 0..config.resamples {
+            for value in resample.iter_mut() {
+                *value = samples[rng.next_index(samples.len())];
+            }
+
+            means.push(stats::mean(&resample));
+            medians.push(stats::median(&mut resample));
+        }
+    } else {
+        let mut resample = vec![(0.0, 0); samples.len()];
+
+        for _ in 0..config.resamples {
+            for value in resample.iter_mut() {
+                let index = rng.next_index(samples.len());
+
+                *value = (samples[index], weights[index]);
+            }
+
+            resample.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            means.push(stats::weighted_mean(&resample));
+            medians.push(stats::weighted_median_of_sorted(&resample));
+        }
+    }
+
+    let interval = |estimate: f64, distribution: Vec<f64>| {
+        let distribution = stats::sorted(&distribution);
+
+        let alpha = (1.0 - config.confidence_level) / 2.0;
+
+        ConfidenceInterval {
+            estimate:         stats::nanos_to_duration(estimate),
+            lower:            stats::nanos_to_duration(stats::percentile_of_sorted(
+                &distribution,
+                alpha,
+            )),
+            upper:            stats::nanos_to_duration(stats::percentile_of_sorted(
+                &distribution,
+                1.0 - alpha,
+            )),
+            confidence_level: config.confidence_level,
+        }
+    };
+
+    let sorted = stats::sorted_weighted(samples, weights);
+
+    Some(Bootstrap {
+        mean:   interval(stats::weighted_mean(&sorted), means),
+        median: interval(stats::weighted_median_of_sorted(&sorted), medians),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_bootstrap() {
+        let samples: Vec<f64> = (0..50).map(|i| f64::from(100 + (i * 37) % 23)).collect();
+
+        for weights in [vec![1; samples.len()], (0..50).map(|i| 1 + i % 4).collect()] {
+            let config = BootstrapConfig {
+                resamples: 1000,
+                seed: 7,
+                ..BootstrapConfig::default()
+            };
+
+            let a = bootstrap(&samples, &weights, &config).unwrap();
+            let b = bootstrap(&samples, &weights, &config).unwrap();
+
+            assert_eq!(a, b);
+            assert!(a.mean.lower <= a.mean.estimate && a.mean.estimate <= a.mean.upper);
+            assert!(a.median.lower <= a.median.upper);
+        }
+    }
+
+    #[test]
+    fn invalid_config_gives_none() {
+        let samples = [1.0, 2.0, 3.0];
+        let weights = [1, 1, 1];
+
+        for config in [
+            BootstrapConfig {
+                resamples: 0,
+                ..BootstrapConfig::default()
+            },
+            BootstrapConfig {
+                confidence_level: 0.0,
+                ..BootstrapConfig::default()
+            },
+            BootstrapConfig {
+                confidence_level: 1.0,
+                ..BootstrapConfig::default()
+            },
+        ] {
+            assert_eq!(None, bootstrap(&samples, &weights, &config));
+        }
+
+        assert_eq!(None, bootstrap(&[], &[], &BootstrapConfig::default()));
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{black_box, stats, Clock, Measurer};
+
+const CALIBRATION_TIMES: usize = 10000;
+const RESOLUTION_TRIALS: usize = 1000;
+/// Detecting the resolution of a clock stops after this long, so that a coarse or stalled clock does not keep it busy-waiting.
+const RESOLUTION_TIME_LIMIT: Duration = Duration::from_millis(50);
+const UNCALIBRATED: u64 = u64::MAX;
+
+static TIMER_OVERHEAD: AtomicU64 = AtomicU64::new(UNCALIBRATED);
+static CLOCK_RESOLUTION: AtomicU64 = AtomicU64::new(UNCALIBRATED);
+static CLOCK_COST: AtomicU64 = AtomicU64::new(UNCALIBRATED);
+/// The clock infos of the clocks used by measurers (`None` for the default clock), so that every clock is only calibrated and the clocksource is only read once.
+static CLOCK_INFOS: Mutex<Vec<(Option<&'static dyn Clock>, ClockInfo)>> = Mutex::new(Vec::new());
+
+/// The empirical properties of a clock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockInfo {
+    /// The smallest observable difference between two readings of the clock. Measurements shorter than this are not trustworthy. It is zero if unknown, such as for a clock which did not change while it was detected.
+    pub resolution:  Duration,
+    /// How long does reading the clock once take.
+    pub cost:        Duration,
+    /// The current clocksource of the Linux kernel (such as `tsc` or `hpet`), if available.
+    pub clocksource: Option<String>,
+}
+
+/// Measure an empty function many times and take the median.
+fn measure_timer_overhead(clock: Option<&'static dyn Clock>) -> Duration {
+    let mut measurer = Measurer::default();
+
+    if let Some(clock) = clock {
+        measurer.set_clock(clock);
+    }
+
+    measurer.set_retain_samples(true);
+
+    for _ in 0..CALIBRATION_TIMES {
+        measurer.measure(|| ());
+    }
+
+    measurer.result.take().and_then(|result| result.median()).unwrap()
+}
+
+/// Measure the fixed cost of `Measurer::measure` itself (reading the default clock twice and `black_box`), by measuring an empty function many times and taking the median. The calibrated value is cached and used by `timer_overhead`.
+pub fn calibrate_timer_overhead() -> Duration {
+    let overhead = measure_timer_overhead(None);
+
+    TIMER_OVERHEAD.store(overhead.as_nanos() as u64, Ordering::Relaxed);
+
+    overhead
+}
+
+/// Measure the fixed cost of `Measurer::measure` itself when it uses a specific clock. The value is not cached.
+#[inline]
+pub fn timer_overhead_of(clock: &'static dyn Clock) -> Duration {
+    measure_timer_overhead(Some(clock))
+}
+
+/// Get the fixed cost of `Measurer::measure` itself with the default clock. It is calibrated by `calibrate_timer_overhead` the first time.
+pub fn timer_overhead() -> Duration {
+    match TIMER_OVERHEAD.load(Ordering::Relaxed) {
+        UNCALIBRATED => calibrate_timer_overhead(),
+        nanos => Duration::from_nanos(nanos),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn clocksource() -> Option<String> {
+    std::fs::read_to_string("/sys/devices/system/clocksource/clocksource0/current_clocksource")
+        .ok()
+        .map(|clocksource| clocksource.trim().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+#[inline]
+fn clocksource() -> Option<String> {
+    None
+}
+
+/// The resolution is the smallest difference between a reading of the clock and the first different reading after it. The trials stop after `RESOLUTION_TIME_LIMIT`, and the resolution is unknown (zero) if the clock never changed by then.
+fn detect_resolution_and_cost<T: Copy + PartialEq>(
+    read: impl Fn() -> T,
+    nanos_between: impl Fn(T, T) -> f64,
+) -> (Duration, Duration) {
+    let deadline = Instant::now() + RESOLUTION_TIME_LIMIT;
+
+    let mut resolution = f64::INFINITY;
+
+    'trials: for _ in 0..RESOLUTION_TRIALS {
+        let start = read();
+
+        let mut now = read();
+
+        while now == start {
+            if Instant::now() >= deadline {
+                break 'trials;
+            }
+
+            now = read();
+        }
+
+        resolution = resolution.min(nanos_between(start, now));
+
+        if Instant::now() >= deadline {
+            break;
+        }
+    }
+
+    if resolution.is_infinite() {
+        resolution = 0.0;
+    }
+
+    let start = read();
+
+    for _ in 0..CALIBRATION_TIMES {
+        black_box(read());
+    }
+
+    let cost = nanos_between(start, read()) / CALIBRATION_TIMES as f64;
+
+    (stats::nanos_to_duration(resolution), stats::nanos_to_duration(cost))
+}
+
+/// Determine the resolution and the cost of the default clock of `Measurer` (`Instant`) empirically. The determined values are cached and used by `clock_info`.
+pub fn calibrate_clock_info() -> ClockInfo {
+    let (resolution, cost) = detect_resolution_and_cost(Instant::now, |start, end| {
+        end.duration_since(start).as_nanos() as f64
+    });
+
+    CLOCK_RESOLUTION.store(resolution.as_nanos() as u64, Ordering::Relaxed);
+    CLOCK_COST.store(cost.as_nanos() as u64, Ordering::Relaxed);
+
+    CLOCK_INFOS.lock().unwrap().retain(|(clock, _)| clock.is_some());
+
+    ClockInfo {
+        resolution,
+        cost,
+        clocksource: clocksource(),
+    }
+}
+
+/// Determine the resolution and the cost of a specific clock empirically. The values are not cached. The resolution is zero if the clock did not change within `50` milliseconds.
+pub fn clock_info_of(clock: &dyn Clock) -> ClockInfo {
+    let (resolution, cost) = detect_resolution_and_cost(
+        || clock.now(),
+        |start, end| clock.to_nanos(end.wrapping_sub(start)),
+    );
+
+    ClockInfo {
+        resolution,
+        cost,
+        clocksource: clocksource(),
+    }
+}
+
+/// Get the resolution and the cost of the default clock of `Measurer`. They are determined by `calibrate_clock_info` the first time.
+pub fn clock_info() -> ClockInfo {
+    match (CLOCK_RESOLUTION.load(Ordering::Relaxed), CLOCK_COST.load(Ordering::Relaxed)) {
+        (UNCALIBRATED, _) | (_, UNCALIBRATED) => calibrate_clock_info(),
+        (resolution, cost) => ClockInfo {
+            resolution:  Duration::from_nanos(resolution),
+            cost:        Duration::from_nanos(cost),
+            clocksource: clocksource(),
+        },
+    }
+}
+
+/// Get the clock info of a specific clock, or of the default clock of `Measurer` if `None`. It is determined by `clock_info_of` (or `clock_info`) the first time for every clock, and cached.
+pub(crate) fn cached_clock_info(clock: Option<&'static dyn Clock>) -> ClockInfo {
+    let is_same = |cached_clock: &Option<&'static dyn Clock>| match (*cached_clock, clock) {
+        (Some(cached_clock), Some(clock)) => std::ptr::eq(cached_clock, clock),
+        (None, None) => true,
+        _ => false,
+    };
+
+    let cached = CLOCK_INFOS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(cached_clock, _)| is_same(cached_clock))
+        .map(|(_, info)| info.clone());
+
+    match cached {
+        Some(info) => info,
+        None => {
+            let info = match clock {
+                Some(clock) => clock_info_of(clock),
+                None => clock_info(),
+            };
+
+            CLOCK_INFOS.lock().unwrap().push((clock, info.clone()));
+
+            info
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct StalledClock;
+
+    impl Clock for StalledClock {
+        fn now(&self) -> u64 {
+            42
+        }
+
+        fn to_nanos(&self, ticks: u64) -> f64 {
+            ticks as f64
+        }
+    }
+
+    #[test]
+    fn stalled_clock_has_unknown_resolution() {
+        let start = Instant::now();
+
+        assert_eq!(Duration::from_secs(0), cached_clock_info(Some(&StalledClock)).resolution);
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // cached
+        let start = Instant::now();
+
+        assert_eq!(Duration::from_secs(0), cached_clock_info(Some(&StalledClock)).resolution);
+
+        assert!(start.elapsed() < RESOLUTION_TIME_LIMIT);
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    fmt::Debug,
+    sync::Once,
+    time::{Duration, Instant},
+};
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+use crate::stats;
+
+/// A clock which can be used by `Measurer` to time iterations.
+///
+/// Implementations are expected to be stateless or to keep their state in statics, so that they can be passed as `&'static dyn Clock`.
+pub trait Clock: Debug + Sync {
+    /// Read the clock. The unit of readings depends on the clock, and only the difference between two readings is meaningful.
+    fn now(&self) -> u64;
+
+    /// Convert the difference between two readings into nanoseconds.
+    fn to_nanos(&self, ticks: u64) -> f64;
+}
+
+#[inline]
+pub(crate) fn elapsed(clock: &dyn Clock, start: u64, end: u64) -> Duration {
+    stats::nanos_to_duration(clock.to_nanos(end.wrapping_sub(start)))
+}
+
+/// The monotonic clock of the standard library (`std::time::Instant`). It is the default clock of `Measurer`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InstantClock;
+
+static INSTANT_BASE_INIT: Once = Once::new();
+static mut INSTANT_BASE: Option<Instant> = None;
+
+impl Clock for InstantClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        INSTANT_BASE_INIT.call_once(|| unsafe {
+            INSTANT_BASE = Some(Instant::now());
+        });
+
+        // the base is only written once above, before any read
+        let base = unsafe { INSTANT_BASE }.unwrap();
+
+        Instant::now().duration_since(base).as_nanos() as u64
+    }
+
+    #[inline]
+    fn to_nanos(&self, ticks: u64) -> f64 {
+        ticks as f64
+    }
+}
+
+/// The time-stamp counter of x86 CPUs (`RDTSC`), which has cycle-level resolution. The CPU is assumed to have an invariant TSC, and its frequency is calibrated against `Instant` the first time it is needed.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TscClock;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+static TSC_TICKS_PER_NANO: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+impl TscClock {
+    /// Determine how many ticks does the time-stamp counter advance per nanosecond, by comparing it with `Instant` for `10` milliseconds. The calibrated value is cached and used by `ticks_per_nano`.
+    pub fn calibrate() -> f64 {
+        let start_instant = Instant::now();
+        let start_ticks = TscClock.now();
+
+        thread::sleep(Duration::from_millis(10));
+
+        let end_ticks = TscClock.now();
+        let nanos = start_instant.elapsed().as_nanos() as f64;
+
+        let ticks_per_nano = end_ticks.wrapping_sub(start_ticks) as f64 / nanos;
+
+        TSC_TICKS_PER_NANO.store(ticks_per_nano.to_bits(), Ordering::Relaxed);
+
+        ticks_per_nano
+    }
+
+    /// Get how many ticks does the time-stamp counter advance per nanosecond. It is calibrated by `calibrate` the first time.
+    #[inline]
+    pub fn ticks_per_nano() -> f64 {
+        match TSC_TICKS_PER_NANO.load(Ordering::Relaxed) {
+            0 => TscClock::calibrate(),
+            bits => f64::from_bits(bits),
+        }
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+impl Clock for TscClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::{_mm_lfence, _rdtsc};
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::{_mm_lfence, _rdtsc};
+
+        // the fence keeps the counter from being read before the preceding instructions complete
+        unsafe {
+            _mm_lfence();
+
+            _rdtsc()
+        }
+    }
+
+    #[inline]
+    fn to_nanos(&self, ticks: u64) -> f64 {
+        ticks as f64 / TscClock::ticks_per_nano()
+    }
+}
+
+#[cfg(unix)]
+#[inline]
+pub(crate) fn clock_gettime(clock_id: libc::clockid_t) -> u64 {
+    let mut timespec: libc::timespec = unsafe { std::mem::zeroed() };
+
+    unsafe {
+        libc::clock_gettime(clock_id, &mut timespec);
+    }
+
+    timespec.tv_sec as u64 * 1_000_000_000 + timespec.tv_nsec as u64
+}
+
+/// The `CLOCK_MONOTONIC_RAW` clock of Linux, which is not affected by NTP frequency adjustments.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonotonicRawClock;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Clock for MonotonicRawClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        clock_gettime(libc::CLOCK_MONOTONIC_RAW)
+    }
+
+    #[inline]
+    fn to_nanos(&self, ticks: u64) -> f64 {
+        ticks as f64
+    }
+}
+
+/// The `CLOCK_THREAD_CPUTIME_ID` clock, which only advances while the current thread is running on a CPU. It suits code which blocks or sleeps.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadCpuTimeClock;
+
+#[cfg(unix)]
+impl Clock for ThreadCpuTimeClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID)
+    }
+
+    #[inline]
+    fn to_nanos(&self, ticks: u64) -> f64 {
+        ticks as f64
+    }
+}
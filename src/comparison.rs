@@ -0,0 +1,202 @@
+use crate::stats;
+
+/// The options of comparing two results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompareConfig {
+    /// A change is statistically significant if its p-value is lower than this. The default value is `0.05`.
+    pub significance_level: f64,
+    /// A relative change whose absolute value is not greater than this is regarded as noise. The default value is `0.02` (2%).
+    pub noise_threshold:    f64,
+}
+
+impl Default for CompareConfig {
+    #[inline]
+    fn default() -> Self {
+        CompareConfig {
+            significance_level: 0.05, noise_threshold: 0.02
+        }
+    }
+}
+
+/// The conclusion of a comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Iterations take significantly less time.
+    Improved,
+    /// Iterations take significantly more time.
+    Regressed,
+    /// The change is within the noise threshold, not statistically significant, or could not be tested because there are not enough iterations.
+    NoSignificantChange,
+}
+
+/// The comparison of a result with a baseline result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    /// The relative change of the average iteration time. For example, `-0.1` means 10% faster than the baseline.
+    pub relative_change:      f64,
+    /// How many times faster than the baseline. For example, `2.0` means twice as fast.
+    pub speedup:              f64,
+    /// The two-sided p-value of Welch's t-test on the iteration times.
+    pub t_test_p_value:       Option<f64>,
+    /// The two-sided p-value of the Mann-Whitney U test (normal approximation) on the iteration times.
+    pub mann_whitney_p_value: Option<f64>,
+    /// Cohen's d of the iteration times. A negative value means faster than the baseline.
+    pub effect_size:          Option<f64>,
+    pub verdict:              Verdict,
+}
+
+/// The summary statistics (in nanoseconds) of one side used in tests.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Summary {
+    pub(crate) count:    f64,
+    pub(crate) mean:     f64,
+    pub(crate) variance: f64,
+}
+
+/// Welch's t-test. It returns the p-value and Cohen's d.
+pub(crate) fn welch_t_test(a: Summary, b: Summary) -> Option<(f64, f64)> {
+    if a.count < 2.0 || b.count < 2.0 {
+        return None;
+    }
+
+    let difference = a.mean - b.mean;
+
+    let pooled_variance =
+        ((a.count - 1.0) * a.variance + (b.count - 1.0) * b.variance) / (a.count + b.count - 2.0);
+
+    let effect_size = if pooled_variance > 0.0 { difference / pooled_variance.sqrt() } else { 0.0 };
+
+    let a_error = a.variance / a.count;
+    let b_error = b.variance / b.count;
+
+    let standard_error = (a_error + b_error).sqrt();
+
+    let p_value = if standard_error > 0.0 {
+        let t = difference / standard_error;
+
+        let df = (a_error + b_error).powi(2)
+            / (a_error.powi(2) / (a.count - 1.0) + b_error.powi(2) / (b.count - 1.0));
+
+        stats::t_test_p_value(t, df)
+    } else if difference == 0.0 {
+        1.0
+    } else {
+        0.0
+    };
+
+    Some((p_value, effect_size))
+}
+
+/// The Mann-Whitney U test with tie correction (normal approximation). It returns the p-value.
+pub(crate) fn mann_whitney_u_test(a: &[f64], b: &[f64]) -> f64 {
+    let mut combined: Vec<(f64, bool)> =
+        a.iter().map(|x| (*x, true)).chain(b.iter().map(|x| (*x, false))).collect();
+
+    combined.sort_unstable_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+
+    let n = combined.len() as f64;
+
+    let mut a_rank_sum = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+
+    while i < combined.len() {
+        let mut j = i + 1;
+
+        while j < combined.len() && combined[j].0 == combined[i].0 {
+            j += 1;
+        }
+
+        // ranks are 1-based, and tied values share the average rank
+        let rank = (i + j + 1) as f64 / 2.0;
+
+        for (_, from_a) in &combined[i..j] {
+            if *from_a {
+                a_rank_sum += rank;
+            }
+        }
+
+        let ties = (j - i) as f64;
+
+        tie_correction += ties * ties * ties - ties;
+
+        i = j;
+    }
+
+    let a_count = a.len() as f64;
+    let b_count = b.len() as f64;
+
+    let u = a_rank_sum - a_count * (a_count + 1.0) / 2.0;
+    let mean = a_count * b_count / 2.0;
+    let variance = a_count * b_count / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+
+    if variance > 0.0 {
+        stats::z_test_p_value((u - mean) / variance.sqrt())
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::tests::assert_close;
+
+    #[test]
+    fn welch_t_test_reference() {
+        let (p_value, effect_size) = welch_t_test(
+            Summary {
+                count: 10.0, mean: 10.0, variance: 1.0
+            },
+            Summary {
+                count: 10.0, mean: 11.6, variance: 1.0
+            },
+        )
+        .unwrap();
+
+        // t = -3.578, df = 18
+        assert_close(0.002151253252761, p_value, 1e-9);
+        assert_close(-1.6, effect_size, 1e-9);
+
+        let (p_value, _) = welch_t_test(
+            Summary {
+                count: 10.0, mean: 10.0, variance: 1.0
+            },
+            Summary {
+                count: 20.0, mean: 11.0, variance: 4.0
+            },
+        )
+        .unwrap();
+
+        // t = -1.826, df = 27.98
+        assert_close(0.078579587869134, p_value, 1e-9);
+    }
+
+    #[test]
+    fn welch_t_test_needs_two_samples() {
+        let one = Summary {
+            count: 1.0, mean: 10.0, variance: 0.0
+        };
+        let two = Summary {
+            count: 2.0, mean: 10.0, variance: 1.0
+        };
+
+        assert_eq!(None, welch_t_test(one, two));
+        assert_eq!(None, welch_t_test(two, one));
+    }
+
+    #[test]
+    fn mann_whitney_u_test_with_ties() {
+        // the ranks of `a` sum to 19.5 (U = 4.5), and the tie correction is 36
+        let p_value =
+            mann_whitney_u_test(&[1.0, 2.0, 2.0, 3.0, 4.0], &[2.0, 3.0, 5.0, 6.0, 6.0, 7.0]);
+
+        assert_close(0.051929537563404, p_value, 1e-6);
+    }
+
+    #[test]
+    fn mann_whitney_u_test_without_difference() {
+        assert_close(1.0, mann_whitney_u_test(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]), 1e-6);
+        assert_eq!(1.0, mann_whitney_u_test(&[5.0, 5.0], &[5.0, 5.0, 5.0]));
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    ops::{Add, AddAssign},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use crate::clock::clock_gettime;
+
+/// The user and system CPU time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTime {
+    pub user:   Duration,
+    pub system: Duration,
+}
+
+impl CpuTime {
+    #[inline]
+    /// Get the sum of the user and system CPU time.
+    pub fn total(&self) -> Duration {
+        self.user + self.system
+    }
+
+    #[inline]
+    pub(crate) fn saturating_sub(self, earlier: CpuTime) -> CpuTime {
+        CpuTime {
+            user:   self.user.checked_sub(earlier.user).unwrap_or_default(),
+            system: self.system.checked_sub(earlier.system).unwrap_or_default(),
+        }
+    }
+}
+
+impl Add for CpuTime {
+    type Output = CpuTime;
+
+    #[inline]
+    fn add(self, rhs: CpuTime) -> CpuTime {
+        CpuTime {
+            user: self.user + rhs.user, system: self.system + rhs.system
+        }
+    }
+}
+
+impl AddAssign for CpuTime {
+    #[inline]
+    fn add_assign(&mut self, rhs: CpuTime) {
+        *self = *self + rhs;
+    }
+}
+
+/// The CPU time of the current thread and of the whole process at a moment, or accumulated over periods. `None` means unavailable on this platform.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CpuTimes {
+    /// Read by `clock_gettime`, in nanoseconds.
+    thread:        Option<Duration>,
+    process:       Option<Duration>,
+    /// Read by `getrusage`. Its resolution is too coarse for short periods, so it is only used to split the totals above into user and system time.
+    thread_split:  Option<CpuTime>,
+    process_split: Option<CpuTime>,
+    /// The elapsed time of the periods.
+    elapsed:       Duration,
+}
+
+impl CpuTimes {
+    #[inline]
+    pub(crate) fn zero() -> CpuTimes {
+        CpuTimes {
+            thread:        Some(Duration::from_secs(0)),
+            process:       Some(Duration::from_secs(0)),
+            thread_split:  Some(CpuTime::default()),
+            process_split: Some(CpuTime::default()),
+            elapsed:       Duration::from_secs(0),
+        }
+    }
+
+    /// Read the CPU time at the start of a period. The precise clocks are read last, right before the period is timed.
+    #[inline]
+    pub(crate) fn start() -> CpuTimes {
+        let process_split = process_rusage();
+        let thread_split = thread_rusage();
+        let process = process_clock();
+        let thread = thread_clock();
+
+        CpuTimes {
+            thread,
+            process,
+            thread_split,
+            process_split,
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    /// Get the CPU time from the start of a period until now. The precise clocks are read first, right after the period is timed.
+    #[inline]
+    pub(crate) fn finish(self, elapsed: Duration) -> CpuTimes {
+        let thread = thread_clock();
+        let process = process_clock();
+        let thread_split = thread_rusage();
+        let process_split = process_rusage();
+
+        CpuTimes {
+            thread: since(thread, self.thread, |now, earlier| {
+                now.checked_sub(earlier).unwrap_or_default()
+            }),
+            process: since(process, self.process, |now, earlier| {
+                now.checked_sub(earlier).unwrap_or_default()
+            }),
+            thread_split: since(thread_split, self.thread_split, CpuTime::saturating_sub),
+            process_split: since(process_split, self.process_split, CpuTime::saturating_sub),
+            elapsed,
+        }
+    }
+
+    /// Accumulate a period. A part stays unknown if either side does not know it.
+    #[inline]
+    pub(crate) fn add(&mut self, other: &CpuTimes) {
+        self.thread = self.thread.and_then(|a| other.thread.map(|b| a + b));
+        self.process = self.process.and_then(|a| other.process.map(|b| a + b));
+        self.thread_split = self.thread_split.and_then(|a| other.thread_split.map(|b| a + b));
+        self.process_split = self.process_split.and_then(|a| other.process_split.map(|b| a + b));
+        self.elapsed += other.elapsed;
+    }
+
+    #[inline]
+    pub(crate) fn thread(&self) -> Option<CpuTime> {
+        self.thread.and_then(|total| self.thread_split.map(|split| split_like(total, split)))
+    }
+
+    #[inline]
+    pub(crate) fn process(&self) -> Option<CpuTime> {
+        self.process.and_then(|total| self.process_split.map(|split| split_like(total, split)))
+    }
+
+    /// The elapsed time minus the CPU time of the current thread. It is computed from the totals, so that the rounding errors of short periods do not accumulate.
+    #[inline]
+    pub(crate) fn off_cpu(&self) -> Option<Duration> {
+        self.thread.map(|thread| self.elapsed.checked_sub(thread).unwrap_or_default())
+    }
+}
+
+#[inline]
+fn since<T>(now: Option<T>, earlier: Option<T>, sub: impl FnOnce(T, T) -> T) -> Option<T> {
+    now.and_then(|now| earlier.map(|earlier| sub(now, earlier)))
+}
+
+/// Split a total into user and system time in the proportion of `split`. It is regarded as user time if `split` is zero.
+#[inline]
+fn split_like(total: Duration, split: CpuTime) -> CpuTime {
+    let user = match (total.as_nanos() * split.user.as_nanos())
+        .checked_div(split.total().as_nanos())
+    {
+        Some(user) => Duration::new((user / 1_000_000_000) as u64, (user % 1_000_000_000) as u32),
+        None => total,
+    };
+
+    CpuTime {
+        user,
+        system: total - user,
+    }
+}
+
+#[cfg(unix)]
+#[inline]
+fn getrusage(who: std::os::raw::c_int) -> Option<CpuTime> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::getrusage(who, &mut usage) } != 0 {
+        return None;
+    }
+
+    let to_duration =
+        |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+
+    Some(CpuTime {
+        user: to_duration(usage.ru_utime), system: to_duration(usage.ru_stime)
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+fn thread_rusage() -> Option<CpuTime> {
+    getrusage(libc::RUSAGE_THREAD)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+#[inline]
+fn thread_rusage() -> Option<CpuTime> {
+    None
+}
+
+#[cfg(unix)]
+#[inline]
+fn process_rusage() -> Option<CpuTime> {
+    getrusage(libc::RUSAGE_SELF)
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn process_rusage() -> Option<CpuTime> {
+    None
+}
+
+#[cfg(unix)]
+#[inline]
+fn thread_clock() -> Option<Duration> {
+    Some(Duration::from_nanos(clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID)))
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn thread_clock() -> Option<Duration> {
+    None
+}
+
+#[cfg(unix)]
+#[inline]
+fn process_clock() -> Option<Duration> {
+    Some(Duration::from_nanos(clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID)))
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn process_clock() -> Option<Duration> {
+    None
+}
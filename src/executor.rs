@@ -0,0 +1,51 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+/// An executor which can drive a future to completion on the current thread. Implement it to let `Measurer::measure_async_with` use the runtime of your application.
+pub trait Executor {
+    /// Run a future to completion and return its output.
+    fn block_on<F: Future>(&self, future: F) -> F::Output;
+}
+
+/// A minimal executor which polls a future on the current thread and parks the thread until the future is woken. It is used by `Measurer::measure_async` and the async functions of the `benchmarking` crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockingExecutor;
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+impl Executor for BlockingExecutor {
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = future;
+
+        // the future is shadowed, so it cannot be moved after being pinned
+        let mut future = unsafe { Pin::new_unchecked(&mut future) };
+
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+
+        let mut context = Context::from_waker(&waker);
+
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+}
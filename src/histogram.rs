@@ -0,0 +1,278 @@
+use std::time::Duration;
+
+/// The number of bits used for the linear sub-buckets in every power-of-two range. With `7` bits, a recorded value is off by at most `1/128` of itself (about two significant digits).
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKET_COUNT: u64 = 1 << SUB_BUCKET_BITS;
+
+/// A log-bucketed (HDR-style) histogram of iteration times. Its memory usage only depends on the longest recorded time, not on how many times are recorded.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count:  u64,
+    min:    u64,
+    max:    u64,
+}
+
+/// A non-empty bucket of a `Histogram`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    /// The lowest time which falls into this bucket.
+    pub low:   Duration,
+    /// The highest time which falls into this bucket.
+    pub high:  Duration,
+    /// How many times fall into this bucket.
+    pub count: u64,
+}
+
+#[inline]
+fn index_of(value: u64) -> usize {
+    if value < SUB_BUCKET_COUNT {
+        value as usize
+    } else {
+        let shift = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+
+        let sub_bucket = (value >> shift) - SUB_BUCKET_COUNT;
+
+        (SUB_BUCKET_COUNT * (shift as u64 + 1) + sub_bucket) as usize
+    }
+}
+
+#[inline]
+fn lowest_of(index: usize) -> u64 {
+    let index = index as u64;
+
+    if index < SUB_BUCKET_COUNT {
+        index
+    } else {
+        let shift = index / SUB_BUCKET_COUNT - 1;
+
+        (SUB_BUCKET_COUNT + index % SUB_BUCKET_COUNT) << shift
+    }
+}
+
+#[inline]
+fn highest_of(index: usize) -> u64 {
+    let index = index as u64;
+
+    if index < SUB_BUCKET_COUNT {
+        index
+    } else {
+        let shift = index / SUB_BUCKET_COUNT - 1;
+
+        lowest_of(index as usize) | ((1 << shift) - 1)
+    }
+}
+
+#[inline]
+fn to_nanos(value: Duration) -> u64 {
+    value.as_nanos().min(u128::from(u64::MAX)) as u64
+}
+
+impl Histogram {
+    #[inline]
+    /// Create an empty histogram.
+    pub fn new() -> Histogram {
+        Histogram {
+            counts: Vec::new(), count: 0, min: u64::MAX, max: 0
+        }
+    }
+
+    #[inline]
+    /// Record a time.
+    pub fn record(&mut self, value: Duration) {
+        self.record_n(value, 1);
+    }
+
+    /// Record a time which occurred `n` times, such as the average time of a batch of `n` iterations.
+    pub fn record_n(&mut self, value: Duration, n: u64) {
+        if n == 0 {
+            return;
+        }
+
+        let value = to_nanos(value);
+
+        let index = index_of(value);
+
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+
+        self.counts[index] += n;
+        self.count += n;
+
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Accumulate another histogram into this one.
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+
+        self.count += other.count;
+
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    #[inline]
+    /// Get how many times have been recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    #[inline]
+    /// Get the shortest recorded time.
+    pub fn min(&self) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(Duration::from_nanos(self.min))
+        }
+    }
+
+    #[inline]
+    /// Get the longest recorded time.
+    pub fn max(&self) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(Duration::from_nanos(self.max))
+        }
+    }
+
+    /// Determine the time below which `p` percent (`0.0` to `100.0`) of the recorded times fall. Like HdrHistogram, the highest time of the matched bucket is returned.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((p.clamp(0.0, 100.0) / 100.0 * self.count as f64).ceil() as u64).max(1);
+
+        let mut accumulated = 0;
+
+        for (index, count) in self.counts.iter().enumerate() {
+            accumulated += count;
+
+            if accumulated >= rank {
+                let value = highest_of(index).min(self.max).max(self.min);
+
+                return Some(Duration::from_nanos(value));
+            }
+        }
+
+        Some(Duration::from_nanos(self.max))
+    }
+
+    /// Iterate over the non-empty buckets, from the shortest times to the longest ones.
+    pub fn buckets(&self) -> impl Iterator<Item = Bucket> + '_ {
+        self.counts.iter().enumerate().filter(|(_, count)| **count > 0).map(|(index, count)| {
+            Bucket {
+                low:   Duration::from_nanos(lowest_of(index)),
+                high:  Duration::from_nanos(highest_of(index)),
+                count: *count,
+            }
+        })
+    }
+}
+
+impl Default for Histogram {
+    #[inline]
+    fn default() -> Self {
+        Histogram::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_boundaries() {
+        assert_eq!(127, index_of(127));
+        assert_eq!(128, index_of(128));
+        assert_eq!(255, index_of(255));
+        assert_eq!(256, index_of(256));
+        assert_eq!(256, index_of(257));
+        assert_eq!(257, index_of(258));
+
+        assert_eq!(127, lowest_of(127));
+        assert_eq!(127, highest_of(127));
+        assert_eq!(128, lowest_of(128));
+        assert_eq!(128, highest_of(128));
+        assert_eq!(255, lowest_of(255));
+        assert_eq!(255, highest_of(255));
+        assert_eq!(256, lowest_of(256));
+        assert_eq!(257, highest_of(256));
+    }
+
+    #[test]
+    fn bucket_of_max() {
+        let index = index_of(u64::MAX);
+
+        assert_eq!(128 * 57 + 127, index);
+        assert_eq!(0xFF << 56, lowest_of(index));
+        assert_eq!(u64::MAX, highest_of(index));
+    }
+
+    #[test]
+    fn buckets_contain_their_values() {
+        let mut value = 1u64;
+
+        while value < u64::MAX / 3 {
+            for value in [value - 1, value, value + 1] {
+                let index = index_of(value);
+
+                assert!(lowest_of(index) <= value && value <= highest_of(index), "{}", value);
+                assert!((highest_of(index) - lowest_of(index)) as f64 <= value as f64 / 128.0);
+            }
+
+            value = value * 3 / 2 + 1;
+        }
+    }
+
+    #[test]
+    fn percentile_bounds() {
+        let mut histogram = Histogram::new();
+
+        assert_eq!(None, histogram.percentile(50.0));
+
+        for value in 1..=1000 {
+            histogram.record(Duration::from_nanos(value));
+        }
+
+        assert_eq!(Some(Duration::from_nanos(1)), histogram.percentile(0.0));
+        assert_eq!(Some(Duration::from_nanos(1000)), histogram.percentile(100.0));
+
+        let median = histogram.percentile(50.0).unwrap().as_nanos();
+
+        assert!((500..=504).contains(&median), "{}", median);
+
+        histogram.record(Duration::from_nanos(u64::MAX));
+
+        assert_eq!(Some(Duration::from_nanos(u64::MAX)), histogram.percentile(100.0));
+        assert_eq!(Some(Duration::from_nanos(1)), histogram.percentile(0.0));
+    }
+
+    #[test]
+    fn record_n_weights_percentiles() {
+        let mut histogram = Histogram::new();
+
+        histogram.record_n(Duration::from_nanos(1000), 999);
+        histogram.record_n(Duration::from_nanos(5), 0);
+        histogram.record(Duration::from_nanos(1_000_000));
+
+        assert_eq!(1000, histogram.count());
+        assert_eq!(Some(Duration::from_nanos(1000)), histogram.min());
+
+        let p90 = histogram.percentile(90.0).unwrap().as_nanos();
+
+        assert!((1000..1008).contains(&p90), "{}", p90);
+        assert_eq!(Some(Duration::from_nanos(1_000_000)), histogram.percentile(100.0));
+    }
+}
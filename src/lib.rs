@@ -103,14 +103,54 @@
 //!
 //! The `warm_up` and `warm_up_with_duration` functions of the `benchmarking` crate runs on one thread. To warm up all CPUs, you can use the `warm_up_multi_thread` and `warm_up_multi_thread_with_duration` functions instead.
 //! The `measure_function` and `measure_function_with_times` functions of the `benchmarking` crate can execute a closure for N times. To execute it repeatly for a while instead, you can use the `bench_function` and `bench_function_with_duration` functions.
-//! To execute a closure with multiple threads to measure the throughput, you can use the `multi_thread_bench_function` and `multi_thread_bench_function_with_duration` functions of the `benchmarking` crate.
-
+//! To execute a closure with multiple threads to measure the throughput, you can use the `multi_thread_bench_function` and `multi_thread_bench_function_with_duration` functions of the `benchmarking` crate. The `elapsed` and `speed` of their results reflect the throughput of all threads together, and `thread_elapsed` and `thread_speed` are those of a single thread.
+//! Instead of a fixed number of runs or a fixed duration, the `measure_function_with_target_time` function (or `Bench::target_time`) times a run and picks the number of runs so that the whole benchmark takes about the target wall time.
+//! To stop as soon as the result is precise enough, use the `bench_function_with_precision` function (or `Bench::precision`) with a target such as `Precision::RelativeStandardError(0.01)` or `Precision::RelativeConfidenceInterval(0.01)`. The benchmark is still bounded by `Bench::min_times`, `Bench::times`, `Bench::min_duration` and `Bench::duration`, and `BenchResult::is_precision_reached` (or `Precision::is_reached_by`) tells whether the target was reached.
+//! All of the above functions are shorthands of the `Bench` builder, which combines the options in one place: `Bench::new().times(n).duration(d).threads(t).measurers(m).warm_up(w).run(f)` (or `run_multi_thread(f)` for more than one thread). The function receives `m` measurers, and the returned `BenchResult` holds one `MeasureResult` for each of them.
+//! Iterations are timed by `Instant` by default. Call `measurer.set_clock(&TscClock)` to count CPU cycles on x86, `measurer.set_clock(&MonotonicRawClock)` to use `CLOCK_MONOTONIC_RAW` on Linux, or `measurer.set_clock(&ThreadCpuTimeClock)` to only count the CPU time of the current thread on Unix-like systems. Other clocks can be used by implementing the `Clock` trait. To use a clock with the `Bench` builder, call `Bench::clock`, which sets it for every measurer. The durations of `bench_function_with_duration` and the like are always wall-clock time.
+//! To know how much of the elapsed time was actually spent computing, call `measurer.set_measure_cpu_time(true)`. Then the `thread_cpu_time`, `process_cpu_time` and `off_cpu_time` methods of the `MeasureResult` report the user and system CPU time.
+//! To find out why a piece of code is slow on Linux, call `measurer.set_measure_perf_events(true)` to count software events (task clock, context switches, CPU migrations and page faults) and hardware events (cycles, instructions, cache misses and branch misses) with `perf_event_open`. Then the `perf_count` and `perf_count_per_iteration` methods of the `MeasureResult` report them. Events which are not permitted or not supported are reported as `None`.
+//! The results of `measure_function_with_times`, `bench_function_with_duration` and `multi_thread_bench_function_with_duration` (and the functions which call them) carry the `getrusage` usage of the run, such as page faults, context switches, the peak resident set size and block I/O operations. Use `MeasureResult::resource_usage` to get it. For the multi-thread functions, it is the sum of all threads, and `BenchResult::resource_usages` of `Bench::run_multi_thread` reports the usage of every thread. No special privileges are needed.
+//! To count heap allocations, install `CountingAllocator` as the global allocator (`#[global_allocator] static GLOBAL: CountingAllocator = CountingAllocator::new(System);`). Then `measurer.measure` records the allocations, deallocations, reallocations and bytes allocated by the current thread inside the measured closure, and the `allocation_stats` and `allocations_per_iteration` (etc.) methods of the `MeasureResult` report them.
+//! To compare the footprints of data structures, use the `measure_memory` function (with `CountingAllocator` installed). It runs a closure once and reports the peak heap bytes during it and the bytes retained by its return value.
+//! Only the function passed to `measurer.measure` is timed. If it consumes an input which has to be prepared (or cloned) for every iteration, use `measurer.measure_with_setup(setup, routine)` to prepare it outside the timed region, or `measurer.measure_batched(setup, routine, BatchSize::SmallInput)` to prepare and measure a batch of inputs at once.
+//! The value returned by the function passed to `measurer.measure` is dropped inside the timed region. To exclude the cost of its destructor, call `measurer.set_defer_drop(true)`, or use `measurer.measure_deferring_drop` for a single measurement. To measure the cost of the destructor alone, use `measurer.measure_drop`.
+//! Operations which take less than a nanosecond or so cannot be timed one by one. Use `measurer.measure_iter(n, f)` to execute a function `n` times between a single pair of clock readings, or `measurer.measure_iter_auto(f)` to choose `n` so that the timed region is far longer than the clock resolution. Then `MeasureResult::elapsed_picos` reports the average time in picoseconds.
+//! For code which cannot be wrapped in a single closure (callbacks, early returns, state machines), call `measurer.start()` to get a guard which records the measurement when it is stopped by `stop` or dropped. The guard can also `pause` and `resume` the timing.
+//! Times measured elsewhere (by another clock, a device or a child process) can be fed into the same result by `measurer.record(elapsed)` or `measurer.record_n(count, total)`.
+//! To measure async code, use `measurer.measure_async(future)`, which drives the future with the bundled `BlockingExecutor` and records the time from its first poll to its completion, or `measurer.measure_async_with(&executor, future)` to use your own runtime by implementing the `Executor` trait. The `measure_async_function_with_times` and `bench_async_function_with_duration` functions measure the futures returned by an async function, and their `_and_executor` variants take an executor. `MeasureResult::polls_per_iteration` reports how many times the futures were polled.
+//! For operations which take only a few nanoseconds, the cost of reading the clock matters. Call `measurer.set_subtract_overhead(true)` to subtract the calibrated timer overhead (see the `timer_overhead` function) from every iteration. The subtracted overhead is reported by `MeasureResult::overhead`. The `clock_info` function reports the empirical resolution and cost of the clock (and the Linux clocksource), and `MeasureResult::clock_info` reports those of the clock which timed a result. If any iteration is shorter than the resolution, `MeasureResult::is_trustworthy` returns `false`.
+//! To measure how much data can be processed per second, declare the amount of data processed by every iteration with `measurer.set_throughput(Some(Throughput::Bytes(n)))`, or by a single measurement with `measurer.measure_with_throughput(Throughput::Bytes(n), f)`. Then the `bytes_per_second` and `nanos_per_byte` (or `elements_per_second` and `nanos_per_element`) methods of the `MeasureResult` can be used.
+//! The min, max, variance and standard deviation of iteration times are always tracked in constant memory. A batch of iterations timed together (by `measure_iter`, `measure_batched` or `record_n`) counts as one observation of its average time for the variance, since how the times spread within it is unknown, but every iteration in it is regarded as taking the average time for the percentiles, the median, etc. To get the median, the median absolute deviation, the trimmed mean, etc., call `measurer.set_retain_samples(true)` before measuring so that every iteration time (or the average time of every batch, with its size as `sample_weights`) is kept in the `MeasureResult`. The retained samples can also be classified into outliers by the `outliers` method, and the `elapsed_without_outliers` and `speed_without_outliers` methods exclude them. The `bootstrap` method computes the confidence intervals of the mean and the median iteration time.
+//! To get percentiles (such as `p99`) of iteration times in constant memory, call `measurer.set_record_histogram(true)` before measuring.
+//! To tell whether a change is real or noise, use `MeasureResult::compare` to compare a result with a baseline result. It reports the relative change, the speedup, the p-values of Welch's t-test and the Mann-Whitney U test, the effect size and a verdict.
+//! Results from separate runs, processes or machines can be combined by `MeasureResult::merge`, `+`, `+=` or `Iterator::sum`. Use `MeasureResult::from_samples` or `MeasureResult::from_total_elapsed` to rebuild a result from recorded times.
+
+mod allocation;
+mod batch_size;
+mod bench;
+mod bootstrap;
+mod calibration;
+mod clock;
+mod comparison;
+mod cpu_time;
+mod executor;
+mod histogram;
 mod measure_result;
 mod measurer;
+mod outliers;
+mod perf;
+mod precision;
+mod resource_usage;
+mod rng;
+mod running_stats;
+mod stats;
+mod throughput;
 
 use std::{
     error::Error,
     fmt::{Display, Error as FmtError, Formatter},
+    future::Future,
     mem::forget,
     ptr::read_volatile,
     sync::{
@@ -118,11 +158,35 @@ use std::{
         mpsc, Arc,
     },
     thread,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
+pub use allocation::{measure_memory, AllocationStats, CountingAllocator, MemoryUsage};
+pub use batch_size::BatchSize;
+pub use bench::{Bench, BenchResult};
+pub use bootstrap::{Bootstrap, BootstrapConfig, ConfidenceInterval};
+pub use calibration::{
+    calibrate_clock_info, calibrate_timer_overhead, clock_info, clock_info_of, timer_overhead,
+    timer_overhead_of, ClockInfo,
+};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use clock::MonotonicRawClock;
+#[cfg(unix)]
+pub use clock::ThreadCpuTimeClock;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub use clock::TscClock;
+pub use clock::{Clock, InstantClock};
+pub use comparison::{CompareConfig, Comparison, Verdict};
+pub use cpu_time::CpuTime;
+pub use executor::{BlockingExecutor, Executor};
+pub use histogram::{Bucket, Histogram};
 pub use measure_result::MeasureResult;
-pub use measurer::Measurer;
+pub use measurer::{MeasureGuard, Measurer};
+pub use outliers::{OutlierClass, OutlierMethod, Outliers};
+pub use perf::PerfEvent;
+pub use precision::Precision;
+pub use resource_usage::ResourceUsage;
+pub use throughput::Throughput;
 
 const DEFAULT_MEASURE_TIMES: u64 = 10;
 const DEFAULT_MEASURE_DURATION: u64 = 5000;
@@ -212,38 +276,20 @@ pub fn measure_function_with_times<F, O>(
 ) -> Result<MeasureResult, BenchmarkError>
 where
     F: FnMut(&mut Measurer) -> O, {
-    debug_assert!(times > 0);
-
-    let mut measurer = Measurer::default();
-
-    black_box(f(&mut measurer));
-
-    let mut measure_result = if measurer.pass {
-        measurer.pass = false;
-        measurer.result = None;
-
-        MeasureResult::empty()
-    } else {
-        measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?
-    };
-
-    for _ in 1..times {
-        black_box(f(&mut measurer));
-
-        if measurer.pass {
-            measurer.pass = false;
-            measurer.result = None;
-        } else {
-            let result = measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?;
-
-            measure_result.times += result.times;
-            measure_result.total_elapsed += result.total_elapsed;
-        }
-
-        measurer.seq += 1;
-    }
+    Bench::new().times(times).run(|measurers| f(&mut measurers[0])).map(BenchResult::into_result)
+}
 
-    Ok(measure_result)
+/// Run a function for about a specific wall time and measure its execution time. The number of runs is calibrated by timing a run, so that it suits both microsecond work and long jobs.
+pub fn measure_function_with_target_time<F, O>(
+    target_time: Duration,
+    mut f: F,
+) -> Result<MeasureResult, BenchmarkError>
+where
+    F: FnMut(&mut Measurer) -> O, {
+    Bench::new()
+        .target_time(target_time)
+        .run(|measurers| f(&mut measurers[0]))
+        .map(BenchResult::into_result)
 }
 
 #[inline]
@@ -261,42 +307,93 @@ pub fn bench_function_with_duration<F, O>(
 ) -> Result<MeasureResult, BenchmarkError>
 where
     F: FnMut(&mut Measurer) -> O, {
-    let mut measurer = Measurer::default();
-
-    black_box(f(&mut measurer));
-
-    let mut measure_result = if measurer.pass {
-        measurer.pass = false;
-        measurer.result = None;
-
-        MeasureResult::empty()
-    } else {
-        measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?
-    };
+    Bench::new()
+        .duration(duration)
+        .run(|measurers| f(&mut measurers[0]))
+        .map(BenchResult::into_result)
+}
 
-    let start = Instant::now();
+#[inline]
+/// Run an async function 10 times and measure the time to completion of its futures.
+pub fn measure_async_function<F, Fut>(f: F) -> Result<MeasureResult, BenchmarkError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future, {
+    measure_async_function_with_times(DEFAULT_MEASURE_TIMES, f)
+}
 
-    loop {
-        black_box(f(&mut measurer));
+#[inline]
+/// Run an async function with a specific times and measure the time to completion of its futures. Every future is driven by `BlockingExecutor`.
+pub fn measure_async_function_with_times<F, Fut>(
+    times: u64,
+    f: F,
+) -> Result<MeasureResult, BenchmarkError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future, {
+    measure_async_function_with_times_and_executor(times, &BlockingExecutor, f)
+}
 
-        if measurer.pass {
-            measurer.pass = false;
-            measurer.result = None;
-        } else {
-            let result = measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?;
+#[inline]
+/// Run an async function with a specific times and measure the time to completion of its futures. Every future is driven by a specific executor.
+pub fn measure_async_function_with_times_and_executor<E, F, Fut>(
+    times: u64,
+    executor: &E,
+    mut f: F,
+) -> Result<MeasureResult, BenchmarkError>
+where
+    E: Executor,
+    F: FnMut() -> Fut,
+    Fut: Future, {
+    measure_function_with_times(times, |measurer| measurer.measure_async_with(executor, f()))
+}
 
-            measure_result.times += result.times;
-            measure_result.total_elapsed += result.total_elapsed;
-        }
+#[inline]
+/// Run an async function for 5 seconds and measure the time to completion of its futures.
+pub fn bench_async_function<F, Fut>(f: F) -> Result<MeasureResult, BenchmarkError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future, {
+    bench_async_function_with_duration(Duration::from_millis(DEFAULT_MEASURE_DURATION), f)
+}
 
-        if start.elapsed() >= duration {
-            break;
-        }
+#[inline]
+/// Run an async function with a specific duration and measure the time to completion of its futures. Every future is driven by `BlockingExecutor`.
+pub fn bench_async_function_with_duration<F, Fut>(
+    duration: Duration,
+    f: F,
+) -> Result<MeasureResult, BenchmarkError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future, {
+    bench_async_function_with_duration_and_executor(duration, &BlockingExecutor, f)
+}
 
-        measurer.seq += 1;
-    }
+#[inline]
+/// Run an async function with a specific duration and measure the time to completion of its futures. Every future is driven by a specific executor.
+pub fn bench_async_function_with_duration_and_executor<E, F, Fut>(
+    duration: Duration,
+    executor: &E,
+    mut f: F,
+) -> Result<MeasureResult, BenchmarkError>
+where
+    E: Executor,
+    F: FnMut() -> Fut,
+    Fut: Future, {
+    bench_function_with_duration(duration, |measurer| measurer.measure_async_with(executor, f()))
+}
 
-    Ok(measure_result)
+/// Run a function until the mean iteration time is precise enough, for at most 5 seconds, and measure its execution time. Use `Precision::is_reached_by` to check whether the target was reached.
+pub fn bench_function_with_precision<F, O>(
+    precision: Precision,
+    mut f: F,
+) -> Result<MeasureResult, BenchmarkError>
+where
+    F: FnMut(&mut Measurer) -> O, {
+    Bench::new()
+        .precision(precision)
+        .run(|measurers| f(&mut measurers[0]))
+        .map(BenchResult::into_result)
 }
 
 #[inline]
@@ -322,103 +419,11 @@ pub fn multi_thread_bench_function_with_duration<F, O>(
 ) -> Result<MeasureResult, BenchmarkError>
 where
     F: Fn(&mut Measurer) -> O + Send + Sync + 'static, {
-    debug_assert!(number_of_threads > 0);
-
-    let (tx, rx) = mpsc::channel();
-
-    let f = Arc::new(f);
-
-    let start = Instant::now();
-
-    for _ in 1..number_of_threads {
-        let tx = tx.clone();
-
-        let f = f.clone();
-
-        thread::spawn(move || {
-            let mut measurer = Measurer::default();
-
-            black_box(f(&mut measurer));
-
-            let mut measure_result = if measurer.pass {
-                measurer.pass = false;
-                measurer.result = None;
-
-                MeasureResult::empty()
-            } else {
-                measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured).unwrap()
-            };
-
-            loop {
-                black_box(f(&mut measurer));
-
-                if measurer.pass {
-                    measurer.pass = false;
-                    measurer.result = None;
-                } else {
-                    let result =
-                        measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured).unwrap();
-
-                    measure_result.times += result.times;
-                    measure_result.total_elapsed += result.total_elapsed;
-                }
-
-                if start.elapsed() >= duration {
-                    break;
-                }
-
-                measurer.seq += 1;
-            }
-
-            tx.send(measure_result).unwrap();
-        });
-    }
-
-    let mut measurer = Measurer::default();
-
-    black_box(f(&mut measurer));
-
-    let mut measure_result = if measurer.pass {
-        measurer.pass = false;
-        measurer.result = None;
-
-        MeasureResult::empty()
-    } else {
-        measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?
-    };
-
-    let start = Instant::now();
-
-    loop {
-        black_box(f(&mut measurer));
-
-        if measurer.pass {
-            measurer.pass = false;
-            measurer.result = None;
-        } else {
-            let result = measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?;
-
-            measure_result.times += result.times;
-            measure_result.total_elapsed += result.total_elapsed;
-        }
-
-        if start.elapsed() >= duration {
-            break;
-        }
-
-        measurer.seq += 1;
-    }
-
-    for _ in 1..number_of_threads {
-        let result = rx.recv().unwrap();
-
-        measure_result.times += result.times;
-        measure_result.total_elapsed += result.total_elapsed;
-    }
-
-    measure_result.total_elapsed /= number_of_threads as u32;
-
-    Ok(measure_result)
+    Bench::new()
+        .duration(duration)
+        .threads(number_of_threads)
+        .run_multi_thread(move |measurers| f(&mut measurers[0]))
+        .map(BenchResult::into_result)
 }
 
 // TODO n
@@ -435,64 +440,11 @@ where
 pub fn measure_function_n_with_times<F, O>(
     n: usize,
     times: u64,
-    mut f: F,
+    f: F,
 ) -> Result<Vec<MeasureResult>, BenchmarkError>
 where
     F: FnMut(&mut [Measurer]) -> O, {
-    debug_assert!(times > 0);
-
-    let mut measurers = {
-        let mut v = Vec::with_capacity(n);
-
-        for _ in 0..n {
-            v.push(Measurer::default());
-        }
-
-        v
-    };
-
-    black_box(f(&mut measurers));
-
-    let mut measure_results = {
-        let mut v = Vec::with_capacity(n);
-
-        for measurer in measurers.iter_mut() {
-            let measure_result = if measurer.pass {
-                measurer.pass = false;
-                measurer.result = None;
-
-                MeasureResult::empty()
-            } else {
-                measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?
-            };
-
-            v.push(measure_result);
-        }
-
-        v
-    };
-
-    for _ in 1..times {
-        black_box(f(&mut measurers));
-
-        for (i, measure_result) in measure_results.iter_mut().enumerate() {
-            let measurer = &mut measurers[i];
-
-            if measurer.pass {
-                measurer.pass = false;
-                measurer.result = None;
-            } else {
-                let result = measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?;
-
-                measure_result.times += result.times;
-                measure_result.total_elapsed += result.total_elapsed;
-            }
-
-            measurer.seq += 1;
-        }
-    }
-
-    Ok(measure_results)
+    Bench::new().times(times).measurers(n).run(f).map(BenchResult::into_results)
 }
 
 #[inline]
@@ -507,68 +459,11 @@ where
 pub fn bench_function_n_with_duration<F, O>(
     n: usize,
     duration: Duration,
-    mut f: F,
+    f: F,
 ) -> Result<Vec<MeasureResult>, BenchmarkError>
 where
     F: FnMut(&mut [Measurer]) -> O, {
-    let mut measurers = {
-        let mut v = Vec::with_capacity(n);
-
-        for _ in 0..n {
-            v.push(Measurer::default());
-        }
-
-        v
-    };
-
-    black_box(f(&mut measurers));
-
-    let mut measure_results = {
-        let mut v = Vec::with_capacity(n);
-
-        for measurer in measurers.iter_mut() {
-            let measure_result = if measurer.pass {
-                measurer.pass = false;
-                measurer.result = None;
-
-                MeasureResult::empty()
-            } else {
-                measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?
-            };
-
-            v.push(measure_result);
-        }
-
-        v
-    };
-
-    let start = Instant::now();
-
-    loop {
-        black_box(f(&mut measurers));
-
-        for (i, measure_result) in measure_results.iter_mut().enumerate() {
-            let measurer = &mut measurers[i];
-
-            if measurer.pass {
-                measurer.pass = false;
-                measurer.result = None;
-            } else {
-                let result = measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?;
-
-                measure_result.times += result.times;
-                measure_result.total_elapsed += result.total_elapsed;
-            }
-
-            measurer.seq += 1;
-        }
-
-        if start.elapsed() >= duration {
-            break;
-        }
-    }
-
-    Ok(measure_results)
+    Bench::new().duration(duration).measurers(n).run(f).map(BenchResult::into_results)
 }
 
 #[inline]
@@ -597,156 +492,12 @@ pub fn multi_thread_bench_function_n_with_duration<F, O>(
 ) -> Result<Vec<MeasureResult>, BenchmarkError>
 where
     F: Fn(&mut [Measurer]) -> O + Send + Sync + 'static, {
-    debug_assert!(number_of_threads > 0);
-
-    let (tx, rx) = mpsc::channel();
-
-    let f = Arc::new(f);
-
-    let start = Instant::now();
-
-    for _ in 1..number_of_threads {
-        let tx = tx.clone();
-
-        let f = f.clone();
-
-        thread::spawn(move || {
-            let mut measurers = {
-                let mut v = Vec::with_capacity(n);
-
-                for _ in 0..n {
-                    v.push(Measurer::default());
-                }
-
-                v
-            };
-
-            black_box(f(&mut measurers));
-
-            let mut measure_results = {
-                let mut v = Vec::with_capacity(n);
-
-                for measurer in measurers.iter_mut() {
-                    let measure_result = if measurer.pass {
-                        measurer.pass = false;
-                        measurer.result = None;
-
-                        MeasureResult::empty()
-                    } else {
-                        measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured).unwrap()
-                    };
-
-                    v.push(measure_result);
-                }
-
-                v
-            };
-
-            loop {
-                black_box(f(&mut measurers));
-
-                for (i, measure_result) in measure_results.iter_mut().enumerate() {
-                    let measurer = &mut measurers[i];
-
-                    if measurer.pass {
-                        measurer.pass = false;
-                        measurer.result = None;
-                    } else {
-                        let result = measurer
-                            .result
-                            .take()
-                            .ok_or(BenchmarkError::MeasurerNotMeasured)
-                            .unwrap();
-
-                        measure_result.times += result.times;
-                        measure_result.total_elapsed += result.total_elapsed;
-                    }
-
-                    measurer.seq += 1;
-                }
-
-                if start.elapsed() >= duration {
-                    break;
-                }
-            }
-
-            tx.send(measure_results).unwrap();
-        });
-    }
-
-    let mut measurers = {
-        let mut v = Vec::with_capacity(n);
-
-        for _ in 0..n {
-            v.push(Measurer::default());
-        }
-
-        v
-    };
-
-    black_box(f(&mut measurers));
-
-    let mut measure_results = {
-        let mut v = Vec::with_capacity(n);
-
-        for measurer in measurers.iter_mut() {
-            let measure_result = if measurer.pass {
-                measurer.pass = false;
-                measurer.result = None;
-
-                MeasureResult::empty()
-            } else {
-                measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?
-            };
-
-            v.push(measure_result);
-        }
-
-        v
-    };
-
-    let start = Instant::now();
-
-    loop {
-        black_box(f(&mut measurers));
-
-        for (i, measure_result) in measure_results.iter_mut().enumerate() {
-            let measurer = &mut measurers[i];
-
-            if measurer.pass {
-                measurer.pass = false;
-                measurer.result = None;
-            } else {
-                let result = measurer.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)?;
-
-                measure_result.times += result.times;
-                measure_result.total_elapsed += result.total_elapsed;
-            }
-
-            measurer.seq += 1;
-        }
-
-        if start.elapsed() >= duration {
-            break;
-        }
-    }
-
-    for _ in 1..number_of_threads {
-        let results = rx.recv().unwrap();
-
-        for (i, result) in results.into_iter().enumerate() {
-            let measure_result = &mut measure_results[i];
-
-            measure_result.times += result.times;
-            measure_result.total_elapsed += result.total_elapsed;
-        }
-
-        for measure_result in measure_results.iter_mut() {
-            measure_result.total_elapsed /= number_of_threads as u32;
-        }
-    }
-
-    Ok(measure_results)
+    Bench::new()
+        .duration(duration)
+        .threads(number_of_threads)
+        .measurers(n)
+        .run_multi_thread(f)
+        .map(BenchResult::into_results)
 }
 
 pub(crate) fn black_box<T>(dummy: T) -> T {
@@ -1,32 +1,300 @@
-use std::time::Duration;
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign},
+    time::Duration,
+};
 
-/// The result of measurement.
+use crate::{
+    bootstrap::bootstrap,
+    comparison::{mann_whitney_u_test, welch_t_test, Summary},
+    cpu_time::CpuTimes,
+    outliers::Fences,
+    perf::PerfTotals,
+    running_stats::RunningStats,
+    stats, AllocationStats, Bootstrap, BootstrapConfig, ClockInfo, CompareConfig, Comparison,
+    CpuTime, Histogram, OutlierClass, OutlierMethod, Outliers, PerfEvent, ResourceUsage,
+    Throughput, Verdict,
+};
+
+/// The result of measurement. Results can be combined by `merge`, `+`, `+=` or `sum`.
 #[derive(Debug, Clone)]
 pub struct MeasureResult {
-    pub(crate) times:         u128,
-    pub(crate) total_elapsed: Duration,
+    pub(crate) times:            u128,
+    pub(crate) total_elapsed:    Duration,
+    /// The sum of the times measured by every thread. It is longer than `total_elapsed` for the results of `Bench::run_multi_thread`, whose `total_elapsed` is divided by the number of threads.
+    pub(crate) thread_elapsed:   Duration,
+    /// Every iteration time (or the average time of every batch) in nanoseconds, if the measurer retains samples.
+    pub(crate) samples:          Option<Vec<f64>>,
+    /// How many iterations every sample stands for, in the same order as `samples`. It is kept if and only if the samples are.
+    pub(crate) sample_weights:   Option<Vec<u64>>,
+    /// `None` if this result is made from a total time, so how iteration times spread is unknown.
+    pub(crate) running:          Option<RunningStats>,
+    pub(crate) histogram:        Option<Histogram>,
+    pub(crate) bytes:            u128,
+    pub(crate) elements:         u128,
+    /// The total timer overhead which has been subtracted.
+    pub(crate) overhead:         Duration,
+    /// The resolution, the cost and the clocksource of the clock. If results measured with different clocks are merged, the info of the coarsest one is kept.
+    pub(crate) clock_info:       Option<ClockInfo>,
+    /// How many iterations are shorter than the resolution of the clock.
+    pub(crate) below_resolution: u128,
+    pub(crate) cpu_times:        Option<CpuTimes>,
+    pub(crate) perf_counts:      Option<PerfTotals>,
+    pub(crate) resource_usage:   Option<ResourceUsage>,
+    pub(crate) allocations:      Option<AllocationStats>,
+    /// How many times the measured futures were polled.
+    pub(crate) polls:            u128,
 }
 
 unsafe impl Sync for MeasureResult {}
 
+/// Combine an optional part of two results. The part is kept if both sides have it, or if the side lacking it has no iterations.
+#[inline]
+fn merge_part<T>(
+    part: Option<T>,
+    times: u128,
+    other_part: Option<T>,
+    other_times: u128,
+    merge: impl FnOnce(&mut T, T),
+) -> Option<T> {
+    match (part, other_part) {
+        (Some(mut part), Some(other_part)) => {
+            merge(&mut part, other_part);
+
+            Some(part)
+        },
+        (None, part) if times == 0 => part,
+        (part, None) if other_times == 0 => part,
+        _ => None,
+    }
+}
+
 impl MeasureResult {
     #[inline]
-    pub(crate) fn new(elapsed: Duration) -> MeasureResult {
+    /// Create a result which has no iterations.
+    pub fn empty() -> MeasureResult {
         MeasureResult {
-            times: 1, total_elapsed: elapsed
+            times:            0,
+            total_elapsed:    Duration::from_secs(0),
+            thread_elapsed:   Duration::from_secs(0),
+            samples:          None,
+            sample_weights:   None,
+            running:          Some(RunningStats::new()),
+            histogram:        None,
+            bytes:            0,
+            elements:         0,
+            overhead:         Duration::from_secs(0),
+            clock_info:       None,
+            below_resolution: 0,
+            cpu_times:        None,
+            perf_counts:      None,
+            resource_usage:   None,
+            allocations:      None,
+            polls:            0,
         }
     }
 
     #[inline]
-    pub(crate) fn empty() -> MeasureResult {
+    /// Create a result which has one iteration.
+    pub fn from_elapsed(elapsed: Duration) -> MeasureResult {
+        let mut result = MeasureResult::empty();
+
+        result.push(elapsed);
+
+        result
+    }
+
+    #[inline]
+    /// Create a result from the number of iterations and their total time. How iteration times spread is unknown, so the statistics other than the average (such as `min` and `std_dev`) are not available, and they are dropped when merging this result into another one.
+    pub fn from_total_elapsed(times: u128, total_elapsed: Duration) -> MeasureResult {
         MeasureResult {
-            times: 0, total_elapsed: Duration::from_secs(0)
+            times,
+            total_elapsed,
+            thread_elapsed: total_elapsed,
+            samples: None,
+            sample_weights: None,
+            running: None,
+            histogram: None,
+            bytes: 0,
+            elements: 0,
+            overhead: Duration::from_secs(0),
+            clock_info: None,
+            below_resolution: 0,
+            cpu_times: None,
+            perf_counts: None,
+            resource_usage: None,
+            allocations: None,
+            polls: 0,
+        }
+    }
+
+    /// Create a result from the time of every iteration. The times are retained as samples.
+    pub fn from_samples<I: IntoIterator<Item = Duration>>(samples: I) -> MeasureResult {
+        let mut result = MeasureResult::empty();
+
+        result.samples = Some(Vec::new());
+        result.sample_weights = Some(Vec::new());
+
+        for elapsed in samples {
+            result.push(elapsed);
+        }
+
+        result
+    }
+
+    #[inline]
+    pub(crate) fn push(&mut self, elapsed: Duration) {
+        self.times += 1;
+
+        self.total_elapsed += elapsed;
+        self.thread_elapsed += elapsed;
+
+        let nanos = elapsed.as_nanos() as f64;
+
+        if let Some(running) = &mut self.running {
+            running.push(nanos);
+        }
+
+        if let (Some(samples), Some(weights)) = (&mut self.samples, &mut self.sample_weights) {
+            samples.push(nanos);
+            weights.push(1);
+        }
+
+        if let Some(histogram) = &mut self.histogram {
+            histogram.record(elapsed);
+        }
+    }
+
+    /// Push a batch of iterations which were timed together. Every iteration is regarded as taking the average time of the batch, so the batch is recorded in the histogram `iterations` times, and it is one sample weighted by `iterations`. For the variance, it is only one observation, since how the times spread within it is unknown.
+    pub(crate) fn push_batch(&mut self, total_elapsed: Duration, iterations: u64) {
+        if iterations == 0 {
+            return;
+        }
+
+        if iterations == 1 {
+            self.push(total_elapsed);
+
+            return;
+        }
+
+        self.times += u128::from(iterations);
+
+        self.total_elapsed += total_elapsed;
+        self.thread_elapsed += total_elapsed;
+
+        let nanos = total_elapsed.as_nanos() as f64 / iterations as f64;
+
+        if let Some(running) = &mut self.running {
+            running.push_weighted(nanos, iterations as f64);
+        }
+
+        if let (Some(samples), Some(weights)) = (&mut self.samples, &mut self.sample_weights) {
+            samples.push(nanos);
+            weights.push(iterations);
+        }
+
+        if let Some(histogram) = &mut self.histogram {
+            histogram.record_n(stats::nanos_to_duration(nanos), iterations);
         }
     }
 
     #[inline]
-    /// Determine how long does an iteration take on average.
+    pub(crate) fn push_throughput(&mut self, throughput: Throughput, iterations: u64) {
+        match throughput {
+            Throughput::Bytes(bytes) => self.bytes += u128::from(bytes) * u128::from(iterations),
+            Throughput::Elements(elements) => {
+                self.elements += u128::from(elements) * u128::from(iterations)
+            },
+        }
+    }
+
+    /// Accumulate another result into this one, as if all of their iterations had been measured together. The samples and the histogram are kept only if both sides have them.
+    pub fn merge(&mut self, other: MeasureResult) {
+        self.running =
+            merge_part(self.running.take(), self.times, other.running, other.times, |a, b| {
+                a.merge(&b)
+            });
+
+        self.samples =
+            merge_part(self.samples.take(), self.times, other.samples, other.times, |a, b| {
+                a.extend(b)
+            });
+
+        self.sample_weights = merge_part(
+            self.sample_weights.take(),
+            self.times,
+            other.sample_weights,
+            other.times,
+            |a, b| a.extend(b),
+        );
+
+        self.histogram =
+            merge_part(self.histogram.take(), self.times, other.histogram, other.times, |a, b| {
+                a.merge(&b)
+            });
+
+        self.cpu_times =
+            merge_part(self.cpu_times.take(), self.times, other.cpu_times, other.times, |a, b| {
+                a.add(&b)
+            });
+
+        self.perf_counts = merge_part(
+            self.perf_counts.take(),
+            self.times,
+            other.perf_counts,
+            other.times,
+            |a, b| a.merge(&b),
+        );
+
+        self.resource_usage = merge_part(
+            self.resource_usage.take(),
+            self.times,
+            other.resource_usage,
+            other.times,
+            |a, b| a.add(&b),
+        );
+
+        self.allocations = merge_part(
+            self.allocations.take(),
+            self.times,
+            other.allocations,
+            other.times,
+            |a, b| a.add(&b),
+        );
+
+        self.times += other.times;
+        self.total_elapsed += other.total_elapsed;
+        self.thread_elapsed += other.thread_elapsed;
+        self.bytes += other.bytes;
+        self.elements += other.elements;
+        self.overhead += other.overhead;
+        self.clock_info = match (self.clock_info.take(), other.clock_info) {
+            (Some(info), Some(other_info)) => {
+                Some(if other_info.resolution > info.resolution { other_info } else { info })
+            },
+            (info, other_info) => info.or(other_info),
+        };
+        self.below_resolution += other.below_resolution;
+        self.polls += other.polls;
+    }
+
+    /// Divide a total over the iterations. It returns `None` if there are no iterations.
+    #[inline]
+    fn per_iteration(&self, total: f64) -> Option<f64> {
+        if self.times == 0 {
+            None
+        } else {
+            Some(total / self.times as f64)
+        }
+    }
+
+    #[inline]
+    /// Determine how long does an iteration take on average. It is zero if there are no iterations.
     pub fn elapsed(&self) -> Duration {
+        if self.times == 0 {
+            return Duration::from_secs(0);
+        }
+
         let nano_secs = self.total_elapsed.as_nanos() / self.times;
 
         let secs = (nano_secs / 1_000_000_000) as u64;
@@ -37,8 +305,18 @@ impl MeasureResult {
     }
 
     #[inline]
-    /// Determine how many iterations can be executed within one second.
+    /// Determine how long does an iteration take on average, in picoseconds. Unlike `elapsed`, it is not truncated to whole nanoseconds, which matters for operations measured by `Measurer::measure_iter`. It is zero if there are no iterations.
+    pub fn elapsed_picos(&self) -> f64 {
+        self.per_iteration(self.total_elapsed.as_nanos() as f64 * 1000.0).unwrap_or(0.0)
+    }
+
+    #[inline]
+    /// Determine how many iterations can be executed within one second. It is zero if there are no iterations.
     pub fn speed(&self) -> f64 {
+        if self.times == 0 {
+            return 0.0;
+        }
+
         (self.times as f64 / self.total_elapsed.as_nanos() as f64) * 1_000_000_000.0
     }
 
@@ -53,4 +331,549 @@ impl MeasureResult {
     pub fn total_elapsed(&self) -> Duration {
         self.total_elapsed
     }
+
+    #[inline]
+    /// Determine how long does an iteration take on average on a single thread. It is the same as `elapsed`, except for the results of `Bench::run_multi_thread` (and the `multi_thread_*` functions), whose `elapsed` is divided by the number of threads so that it reflects the throughput of all threads together. It is zero if there are no iterations.
+    pub fn thread_elapsed(&self) -> Duration {
+        stats::nanos_to_duration(
+            self.per_iteration(self.thread_elapsed.as_nanos() as f64).unwrap_or(0.0),
+        )
+    }
+
+    #[inline]
+    /// Determine how many iterations can be executed within one second on a single thread. It is the same as `speed`, except for the results of `Bench::run_multi_thread`, whose `speed` is that of all threads together. It is zero if there are no iterations.
+    pub fn thread_speed(&self) -> f64 {
+        if self.times == 0 {
+            return 0.0;
+        }
+
+        (self.times as f64 / self.thread_elapsed.as_nanos() as f64) * 1_000_000_000.0
+    }
+
+    #[inline]
+    /// Get the timer overhead which has been subtracted from an iteration on average. It is zero if the measurer did not subtract the timer overhead.
+    pub fn overhead(&self) -> Duration {
+        stats::nanos_to_duration(self.per_iteration(self.overhead.as_nanos() as f64).unwrap_or(0.0))
+    }
+
+    #[inline]
+    /// Get the resolution of the clock used for measuring. It is zero if unknown.
+    pub fn clock_resolution(&self) -> Duration {
+        self.clock_info.as_ref().map_or(Duration::from_secs(0), |info| info.resolution)
+    }
+
+    #[inline]
+    /// Get the resolution, the cost and the Linux clocksource of the clock used for measuring. If results measured with different clocks were merged, the info of the coarsest one is kept. It returns `None` if nothing was timed by a measurer (such as a result made by `from_samples` or `Measurer::record`).
+    pub fn clock_info(&self) -> Option<&ClockInfo> {
+        self.clock_info.as_ref()
+    }
+
+    #[inline]
+    /// Get how many iterations are shorter than the resolution of the clock.
+    pub fn below_resolution(&self) -> u128 {
+        self.below_resolution
+    }
+
+    #[inline]
+    /// Check whether every iteration is longer than the resolution of the clock. If not, the iteration times are not trustworthy.
+    pub fn is_trustworthy(&self) -> bool {
+        self.below_resolution == 0
+    }
+
+    #[inline]
+    /// Get the total user and system CPU time of the thread which executed the measurements. The total is read by `clock_gettime` in nanoseconds, and it is split in the proportion reported by `getrusage`. It needs the measurer to measure the CPU time, and it is only available on Linux.
+    pub fn thread_cpu_time(&self) -> Option<CpuTime> {
+        self.cpu_times.and_then(|cpu_times| cpu_times.thread())
+    }
+
+    #[inline]
+    /// Get the total user and system CPU time of the whole process during the measurements. It needs the measurer to measure the CPU time, and it is only available on Unix-like systems.
+    pub fn process_cpu_time(&self) -> Option<CpuTime> {
+        self.cpu_times.and_then(|cpu_times| cpu_times.process())
+    }
+
+    #[inline]
+    /// Get the total time the measured thread was not running on a CPU (waiting for I/O, a lock, etc.) during the measurements. It is the total elapsed time minus the total thread CPU time. It needs the measurer to measure the CPU time, and it is only available on Unix-like systems.
+    pub fn off_cpu_time(&self) -> Option<Duration> {
+        self.cpu_times.and_then(|cpu_times| cpu_times.off_cpu())
+    }
+
+    #[inline]
+    /// Get the total count of a performance event during the measurements. The counters are enabled and disabled as a group right around every measurement, and the median count of doing so around nothing is subtracted. It needs the measurer to count performance events, and the event to be available.
+    pub fn perf_count(&self, event: PerfEvent) -> Option<u64> {
+        self.perf_counts.as_ref().and_then(|counts| counts.count(event))
+    }
+
+    #[inline]
+    /// Get the average count of a performance event per iteration.
+    pub fn perf_count_per_iteration(&self, event: PerfEvent) -> Option<f64> {
+        self.perf_count(event).and_then(|count| self.per_iteration(count as f64))
+    }
+
+    #[inline]
+    /// Get the page faults, context switches, peak memory and block I/O during the benchmark run (including the code outside `measure`), reported by `getrusage`. It is available on Unix-like systems for the functions of the `benchmarking` crate which use a single measurer, and only on Linux for the multi-thread ones, where the usages of all threads are summed. Use `Bench::run_multi_thread` and `BenchResult::resource_usages` to get the usage of every thread.
+    pub fn resource_usage(&self) -> Option<ResourceUsage> {
+        self.resource_usage
+    }
+
+    #[inline]
+    /// Get how many times the heap was used by the measured closures in total. It needs `CountingAllocator` to be the global allocator.
+    pub fn allocation_stats(&self) -> Option<AllocationStats> {
+        self.allocations
+    }
+
+    #[inline]
+    /// Get the average number of allocations per iteration.
+    pub fn allocations_per_iteration(&self) -> Option<f64> {
+        self.allocations.and_then(|stats| self.per_iteration(stats.allocations as f64))
+    }
+
+    #[inline]
+    /// Get the average number of deallocations per iteration.
+    pub fn deallocations_per_iteration(&self) -> Option<f64> {
+        self.allocations.and_then(|stats| self.per_iteration(stats.deallocations as f64))
+    }
+
+    #[inline]
+    /// Get the average number of reallocations per iteration.
+    pub fn reallocations_per_iteration(&self) -> Option<f64> {
+        self.allocations.and_then(|stats| self.per_iteration(stats.reallocations as f64))
+    }
+
+    #[inline]
+    /// Get the average number of bytes allocated per iteration.
+    pub fn bytes_allocated_per_iteration(&self) -> Option<f64> {
+        self.allocations.and_then(|stats| self.per_iteration(stats.bytes_allocated as f64))
+    }
+
+    #[inline]
+    /// Get how many times the futures measured by `Measurer::measure_async` were polled in total.
+    pub fn polls(&self) -> u128 {
+        self.polls
+    }
+
+    #[inline]
+    /// Get the average number of polls per iteration. A future which never waits is polled once. It is zero if there are no iterations.
+    pub fn polls_per_iteration(&self) -> f64 {
+        self.per_iteration(self.polls as f64).unwrap_or(0.0)
+    }
+
+    #[inline]
+    /// Get how many bytes have been processed by all measurements.
+    pub fn bytes(&self) -> u128 {
+        self.bytes
+    }
+
+    #[inline]
+    /// Get how many elements have been processed by all measurements.
+    pub fn elements(&self) -> u128 {
+        self.elements
+    }
+
+    #[inline]
+    /// Determine how many bytes can be processed within one second. It needs a declared throughput in bytes.
+    pub fn bytes_per_second(&self) -> Option<f64> {
+        if self.bytes == 0 {
+            None
+        } else {
+            Some(self.bytes as f64 / self.total_elapsed.as_nanos() as f64 * 1_000_000_000.0)
+        }
+    }
+
+    #[inline]
+    /// Determine how many elements can be processed within one second. It needs a declared throughput in elements.
+    pub fn elements_per_second(&self) -> Option<f64> {
+        if self.elements == 0 {
+            None
+        } else {
+            Some(self.elements as f64 / self.total_elapsed.as_nanos() as f64 * 1_000_000_000.0)
+        }
+    }
+
+    #[inline]
+    /// Determine how many nanoseconds does processing a byte take. It needs a declared throughput in bytes.
+    pub fn nanos_per_byte(&self) -> Option<f64> {
+        if self.bytes == 0 {
+            None
+        } else {
+            Some(self.total_elapsed.as_nanos() as f64 / self.bytes as f64)
+        }
+    }
+
+    #[inline]
+    /// Determine how many nanoseconds does processing an element take. It needs a declared throughput in elements.
+    pub fn nanos_per_element(&self) -> Option<f64> {
+        if self.elements == 0 {
+            None
+        } else {
+            Some(self.total_elapsed.as_nanos() as f64 / self.elements as f64)
+        }
+    }
+
+    #[inline]
+    /// Get the time of every iteration in nanoseconds. Iterations which were timed together in a batch (such as by `Measurer::measure_iter`) are one sample of their average time, weighted by `sample_weights`. It returns `None` if the measurer did not retain samples.
+    pub fn samples(&self) -> Option<&[f64]> {
+        match &self.samples {
+            Some(samples) if !samples.is_empty() => Some(samples.as_slice()),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    /// Get how many iterations every sample stands for, in the same order as `samples`. It is `1` for an iteration timed alone, and the size of the batch for iterations timed together.
+    pub fn sample_weights(&self) -> Option<&[u64]> {
+        self.samples().and(self.sample_weights.as_deref())
+    }
+
+    /// The retained samples with their weights, sorted by time.
+    #[inline]
+    fn sorted_samples(&self) -> Option<Vec<(f64, u64)>> {
+        self.samples()
+            .zip(self.sample_weights.as_ref())
+            .map(|(samples, weights)| stats::sorted_weighted(samples, weights))
+    }
+
+    #[inline]
+    fn running(&self) -> Option<&RunningStats> {
+        self.running.as_ref().filter(|running| running.count > 0)
+    }
+
+    #[inline]
+    /// Determine the shortest iteration time.
+    pub fn min(&self) -> Option<Duration> {
+        self.running().map(|running| stats::nanos_to_duration(running.min))
+    }
+
+    #[inline]
+    /// Determine the longest iteration time.
+    pub fn max(&self) -> Option<Duration> {
+        self.running().map(|running| stats::nanos_to_duration(running.max))
+    }
+
+    /// Determine the median iteration time. It needs retained samples. Every sample is counted as many times as the iterations it stands for.
+    pub fn median(&self) -> Option<Duration> {
+        self.sorted_samples()
+            .map(|sorted| stats::nanos_to_duration(stats::weighted_median_of_sorted(&sorted)))
+    }
+
+    #[inline]
+    /// Determine the sample variance of iteration times, in square nanoseconds.
+    pub fn variance(&self) -> Option<f64> {
+        self.running().map(|running| running.variance())
+    }
+
+    #[inline]
+    /// Determine the sample standard deviation of iteration times.
+    pub fn std_dev(&self) -> Option<Duration> {
+        self.running().map(|running| stats::nanos_to_duration(running.variance().sqrt()))
+    }
+
+    /// Determine the median absolute deviation (unscaled) of iteration times. It needs retained samples. Every sample is counted as many times as the iterations it stands for.
+    pub fn median_absolute_deviation(&self) -> Option<Duration> {
+        self.sorted_samples()
+            .map(|sorted| stats::nanos_to_duration(stats::weighted_mad_of_sorted(&sorted)))
+    }
+
+    /// Determine the mean iteration time after discarding the `proportion` (clamped to `0.0` to `0.5`) of the shortest and the longest iterations. It needs retained samples, and returns `None` if no iteration is left after trimming. Every sample is counted as many times as the iterations it stands for.
+    pub fn trimmed_mean(&self, proportion: f64) -> Option<Duration> {
+        let proportion = if proportion > 0.0 { proportion.min(0.5) } else { 0.0 };
+
+        self.sorted_samples().and_then(|sorted| {
+            stats::weighted_trimmed_mean_of_sorted(&sorted, proportion)
+                .map(stats::nanos_to_duration)
+        })
+    }
+
+    /// Determine the coefficient of variation (the standard deviation divided by the mean) of iteration times.
+    pub fn coefficient_of_variation(&self) -> Option<f64> {
+        self.running().map(|running| {
+            if running.mean > 0.0 {
+                running.variance().sqrt() / running.mean
+            } else {
+                0.0
+            }
+        })
+    }
+
+    /// Determine the relative standard error (the standard error of the mean divided by the mean) of iteration times. It needs at least two observations, where a batch of iterations timed together counts as one.
+    pub fn relative_standard_error(&self) -> Option<f64> {
+        self.running()
+            .filter(|running| running.count > 1 && running.mean > 0.0)
+            .map(|running| running.variance().sqrt() / (running.count as f64).sqrt() / running.mean)
+    }
+
+    #[inline]
+    /// Get the histogram of iteration times. It returns `None` if the measurer did not record a histogram.
+    pub fn histogram(&self) -> Option<&Histogram> {
+        self.histogram.as_ref()
+    }
+
+    /// Determine the iteration time below which `p` percent (`0.0` to `100.0`) of iterations fall. It needs a recorded histogram or retained samples. Iterations which were timed together in a batch are all regarded as taking the average time of the batch.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        match &self.histogram {
+            Some(histogram) => histogram.percentile(p),
+            None => self.sorted_samples().map(|sorted| {
+                stats::nanos_to_duration(stats::weighted_percentile_of_sorted(&sorted, p / 100.0))
+            }),
+        }
+    }
+
+    #[inline]
+    /// Determine the 50th percentile of iteration times.
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    #[inline]
+    /// Determine the 90th percentile of iteration times.
+    pub fn p90(&self) -> Option<Duration> {
+        self.percentile(90.0)
+    }
+
+    #[inline]
+    /// Determine the 99th percentile of iteration times.
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    #[inline]
+    /// Determine the 99.9th percentile of iteration times.
+    pub fn p999(&self) -> Option<Duration> {
+        self.percentile(99.9)
+    }
+
+    /// Classify every retained sample. The returned classes are in the same order as `samples`. The fences are determined from the samples weighted by `sample_weights`.
+    pub fn classify_outliers(&self, method: OutlierMethod) -> Option<Vec<OutlierClass>> {
+        self.samples().zip(self.sorted_samples()).map(|(samples, sorted)| {
+            let fences = Fences::new(method, &sorted);
+
+            samples.iter().map(|sample| fences.classify(*sample)).collect()
+        })
+    }
+
+    /// Count the mild and severe outliers among the retained samples.
+    pub fn outliers(&self, method: OutlierMethod) -> Option<Outliers> {
+        self.classify_outliers(method).map(|classes| {
+            let mut outliers = Outliers::default();
+
+            for class in classes {
+                outliers.count(class);
+            }
+
+            outliers
+        })
+    }
+
+    /// The ratio of the weighted mean of the samples which are not outliers to the weighted mean of all samples.
+    fn outlier_free_ratio(&self, method: OutlierMethod) -> Option<f64> {
+        self.sorted_samples().map(|sorted| {
+            let fences = Fences::new(method, &sorted);
+
+            let kept: Vec<(f64, u64)> = sorted
+                .iter()
+                .cloned()
+                .filter(|(sample, _)| !fences.classify(*sample).is_outlier())
+                .collect();
+
+            let mean = stats::weighted_mean(&sorted);
+
+            if mean > 0.0 {
+                stats::weighted_mean(&kept) / mean
+            } else {
+                1.0
+            }
+        })
+    }
+
+    /// Determine how long does an iteration take on average, excluding the outliers (mild and severe). It needs retained samples.
+    pub fn elapsed_without_outliers(&self, method: OutlierMethod) -> Option<Duration> {
+        self.outlier_free_ratio(method).map(|ratio| {
+            stats::nanos_to_duration(
+                self.total_elapsed.as_nanos() as f64 / self.times as f64 * ratio,
+            )
+        })
+    }
+
+    /// Determine how many iterations can be executed within one second, excluding the outliers (mild and severe). It needs retained samples.
+    pub fn speed_without_outliers(&self, method: OutlierMethod) -> Option<f64> {
+        self.outlier_free_ratio(method).map(|ratio| self.speed() / ratio)
+    }
+
+    /// Compute the bootstrap confidence intervals of the mean and the median iteration time. It needs retained samples and a valid config (see `BootstrapConfig::is_valid`). The samples are resampled with their weights, so a batch of iterations is resampled as a whole.
+    pub fn bootstrap(&self, config: &BootstrapConfig) -> Option<Bootstrap> {
+        self.samples()
+            .zip(self.sample_weights.as_ref())
+            .and_then(|(samples, weights)| bootstrap(samples, weights, config))
+    }
+
+    /// The summary statistics of iteration times used in statistical tests.
+    fn summary(&self) -> Option<Summary> {
+        self.running().map(|running| Summary {
+            count:    running.count as f64,
+            mean:     running.mean,
+            variance: running.variance(),
+        })
+    }
+
+    #[inline]
+    /// Compare this result with a baseline result, using the default `CompareConfig`. It returns `None` if either result has no iteration or took no time.
+    pub fn compare(&self, baseline: &MeasureResult) -> Option<Comparison> {
+        self.compare_with(baseline, &CompareConfig::default())
+    }
+
+    /// Compare this result with a baseline result. It returns `None` if either result has no iteration or took no time. The Mann-Whitney U test needs retained samples on both sides. If neither test can be performed, the verdict is `NoSignificantChange`.
+    pub fn compare_with(
+        &self,
+        baseline: &MeasureResult,
+        config: &CompareConfig,
+    ) -> Option<Comparison> {
+        if self.times == 0 || baseline.times == 0 {
+            return None;
+        }
+
+        let mean = self.total_elapsed.as_nanos() as f64 / self.times as f64;
+        let baseline_mean = baseline.total_elapsed.as_nanos() as f64 / baseline.times as f64;
+
+        if mean <= 0.0 || baseline_mean <= 0.0 {
+            return None;
+        }
+
+        let relative_change = (mean - baseline_mean) / baseline_mean;
+        let speedup = baseline_mean / mean;
+
+        let (t_test_p_value, effect_size) = match (self.summary(), baseline.summary()) {
+            (Some(summary), Some(baseline_summary)) => {
+                match welch_t_test(summary, baseline_summary) {
+                    Some((p_value, effect_size)) => (Some(p_value), Some(effect_size)),
+                    None => (None, None),
+                }
+            },
+            _ => (None, None),
+        };
+
+        let mann_whitney_p_value = match (self.samples(), baseline.samples()) {
+            (Some(samples), Some(baseline_samples)) => {
+                Some(mann_whitney_u_test(samples, baseline_samples))
+            },
+            _ => None,
+        };
+
+        let significant = match t_test_p_value.or(mann_whitney_p_value) {
+            Some(p_value) => p_value < config.significance_level,
+            None => false,
+        };
+
+        let verdict = if !significant || relative_change.abs() <= config.noise_threshold {
+            Verdict::NoSignificantChange
+        } else if relative_change < 0.0 {
+            Verdict::Improved
+        } else {
+            Verdict::Regressed
+        };
+
+        Some(Comparison {
+            relative_change,
+            speedup,
+            t_test_p_value,
+            mann_whitney_p_value,
+            effect_size,
+            verdict,
+        })
+    }
+}
+
+impl Default for MeasureResult {
+    #[inline]
+    fn default() -> Self {
+        MeasureResult::empty()
+    }
+}
+
+impl Add for MeasureResult {
+    type Output = MeasureResult;
+
+    #[inline]
+    fn add(mut self, rhs: MeasureResult) -> MeasureResult {
+        self.merge(rhs);
+
+        self
+    }
+}
+
+impl AddAssign for MeasureResult {
+    #[inline]
+    fn add_assign(&mut self, rhs: MeasureResult) {
+        self.merge(rhs);
+    }
+}
+
+impl Sum for MeasureResult {
+    #[inline]
+    fn sum<I: Iterator<Item = MeasureResult>>(iter: I) -> MeasureResult {
+        iter.fold(MeasureResult::empty(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a MeasureResult> for MeasureResult {
+    #[inline]
+    fn sum<I: Iterator<Item = &'a MeasureResult>>(iter: I) -> MeasureResult {
+        iter.cloned().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_result() {
+        let result: MeasureResult = Vec::<MeasureResult>::new().into_iter().sum();
+
+        assert_eq!(0, result.times());
+        assert_eq!(Duration::from_secs(0), result.elapsed());
+        assert_eq!(0.0, result.elapsed_picos());
+        assert_eq!(0.0, result.speed());
+        assert_eq!(Duration::from_secs(0), result.thread_elapsed());
+        assert_eq!(0.0, result.thread_speed());
+        assert_eq!(Duration::from_secs(0), result.overhead());
+        assert_eq!(0.0, result.polls_per_iteration());
+        assert_eq!(None, result.min());
+        assert_eq!(None, result.std_dev());
+    }
+
+    #[test]
+    fn batches_are_weighted_by_iterations() {
+        let mut result = MeasureResult::empty();
+
+        result.samples = Some(Vec::new());
+        result.sample_weights = Some(Vec::new());
+        result.histogram = Some(Histogram::new());
+
+        result.push_batch(Duration::from_micros(999), 999);
+        result.push(Duration::from_millis(1));
+
+        assert_eq!(1000, result.times());
+        assert_eq!(1000, result.histogram().unwrap().count());
+        assert_eq!(Some(&[999, 1][..]), result.sample_weights());
+
+        let p90 = result.p90().unwrap().as_nanos();
+
+        assert!((1000..1004).contains(&p90), "{}", p90);
+
+        result.histogram = None;
+
+        assert_eq!(Some(Duration::from_micros(1)), result.p90());
+        assert_eq!(Some(Duration::from_millis(1)), result.percentile(100.0));
+        assert_eq!(Some(Duration::from_micros(1)), result.median());
+        assert_eq!(Some(Duration::from_secs(0)), result.median_absolute_deviation());
+        assert_eq!(Some(Duration::from_micros(1)), result.trimmed_mean(0.1));
+
+        let outliers = result.outliers(OutlierMethod::Tukey).unwrap();
+
+        assert_eq!(1, outliers.high_severe);
+        assert_eq!(
+            Some(Duration::from_micros(1)),
+            result.elapsed_without_outliers(OutlierMethod::Tukey)
+        );
+    }
 }
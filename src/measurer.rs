@@ -1,6 +1,38 @@
-use std::time::{Duration, Instant};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
 
-use crate::{black_box, MeasureResult};
+use crate::{
+    allocation::allocation_stats,
+    black_box,
+    calibration::cached_clock_info,
+    clock,
+    cpu_time::CpuTimes,
+    perf::{PerfCounters, PerfCounts, PerfTotals},
+    timer_overhead, timer_overhead_of, AllocationStats, BatchSize, BenchmarkError,
+    BlockingExecutor, Clock, ClockInfo, Executor, Histogram, MeasureResult, Throughput,
+};
+
+/// `measure_iter_auto` makes a measurement take at least this many times the clock resolution.
+const AUTO_ITERATIONS_RESOLUTION_FACTOR: u32 = 1000;
+
+/// The readings taken right before timing an iteration, or their deltas taken right after it. The performance counters are reset when they start, so they only have deltas.
+#[derive(Debug)]
+struct Probe {
+    cpu_times:   Option<CpuTimes>,
+    perf_counts: Option<PerfCounts>,
+    allocations: Option<AllocationStats>,
+}
+
+/// A reading of the clock of a measurer.
+#[derive(Debug, Clone, Copy)]
+enum ClockReading {
+    Clock(u64),
+    Instant(Instant),
+}
 
 #[derive(Debug, Default)]
 /// To measure the execution time.
@@ -8,6 +40,22 @@ pub struct Measurer {
     pub(crate) seq:    u128,
     pub(crate) result: Option<MeasureResult>,
     pub(crate) pass:   bool,
+    retain_samples:    bool,
+    record_histogram:  bool,
+    throughput:        Option<Throughput>,
+    /// The timer overhead to subtract from every iteration, if enabled.
+    overhead:          Option<Duration>,
+    /// The resolution, the cost and the clocksource of the clock. They are determined at the first measurement, and every clock is only calibrated once per process.
+    clock_info:        Option<ClockInfo>,
+    /// `None` means the default clock, `Instant`.
+    clock:             Option<&'static dyn Clock>,
+    measure_cpu_time:  bool,
+    /// The performance counters opened for the current thread, if enabled.
+    perf_counters:     Option<PerfCounters>,
+    /// Whether to drop the values returned by measured functions after the timer stops.
+    defer_drop:        bool,
+    /// The number of iterations per measurement chosen by `measure_iter_auto`. It is determined at the first call.
+    auto_iterations:   Option<u64>,
 }
 
 impl Measurer {
@@ -36,29 +84,539 @@ impl Measurer {
     }
 
     #[inline]
-    fn update(&mut self, elapsed: Duration) {
-        match &mut self.result {
-            Some(result) => {
-                result.times += 1;
+    /// Check this measurer whether it retains the time of every iteration.
+    pub fn is_retaining_samples(&self) -> bool {
+        self.retain_samples
+    }
+
+    #[inline]
+    /// Set whether to retain the time of every iteration, so that the result can report the median, outliers, confidence intervals, etc. It should be set before measuring.
+    pub fn set_retain_samples(&mut self, retain_samples: bool) {
+        self.retain_samples = retain_samples;
+    }
+
+    #[inline]
+    /// Check this measurer whether it records a histogram of iteration times.
+    pub fn is_recording_histogram(&self) -> bool {
+        self.record_histogram
+    }
+
+    #[inline]
+    /// Set whether to record a log-bucketed histogram of iteration times, so that the result can report percentiles in constant memory. It should be set before measuring.
+    pub fn set_record_histogram(&mut self, record_histogram: bool) {
+        self.record_histogram = record_histogram;
+    }
+
+    #[inline]
+    /// Get the amount of data processed by every iteration.
+    pub fn get_throughput(&self) -> Option<Throughput> {
+        self.throughput
+    }
+
+    #[inline]
+    /// Set the amount of data processed by every iteration, so that the result can report bytes or elements per second. It can be overridden for a single measurement by `measure_with_throughput`.
+    pub fn set_throughput(&mut self, throughput: Option<Throughput>) {
+        self.throughput = throughput;
+    }
+
+    #[inline]
+    /// Check this measurer whether it subtracts the timer overhead from every iteration.
+    pub fn is_subtracting_overhead(&self) -> bool {
+        self.overhead.is_some()
+    }
+
+    #[inline]
+    /// Set whether to subtract the timer overhead (see `timer_overhead`) from every iteration. It matters for operations which take only a few nanoseconds. It should be set before measuring.
+    pub fn set_subtract_overhead(&mut self, subtract_overhead: bool) {
+        self.overhead = if subtract_overhead {
+            Some(self.overhead.unwrap_or_else(|| self.calibrate_overhead()))
+        } else {
+            None
+        };
+    }
+
+    #[inline]
+    fn calibrate_overhead(&self) -> Duration {
+        match self.clock {
+            Some(clock) => timer_overhead_of(clock),
+            None => timer_overhead(),
+        }
+    }
+
+    #[inline]
+    /// Set the clock used to time iterations. The default clock is `Instant` (see `InstantClock`). The timer overhead and the resolution are calibrated again for a different clock.
+    pub fn set_clock(&mut self, clock: &'static dyn Clock) {
+        if let Some(current_clock) = self.clock {
+            if std::ptr::eq(current_clock, clock) {
+                return;
+            }
+        }
+
+        self.clock = Some(clock);
+        self.clock_info = None;
+
+        if self.overhead.is_some() {
+            self.overhead = Some(self.calibrate_overhead());
+        }
+    }
+
+    #[inline]
+    /// Check this measurer whether it measures the CPU time.
+    pub fn is_measuring_cpu_time(&self) -> bool {
+        self.measure_cpu_time
+    }
+
+    #[inline]
+    /// Set whether to measure the user and system CPU time of the current thread and of the whole process for every iteration, so that the result can report how much of the elapsed time was spent computing. It should be set before measuring.
+    pub fn set_measure_cpu_time(&mut self, measure_cpu_time: bool) {
+        self.measure_cpu_time = measure_cpu_time;
+    }
+
+    #[inline]
+    /// Check this measurer whether it counts performance events.
+    pub fn is_measuring_perf_events(&self) -> bool {
+        self.perf_counters.is_some()
+    }
+
+    #[inline]
+    /// Set whether to count the performance events of Linux (see `PerfEvent`) for every iteration, so that the result can explain timing differences. Events which cannot be opened (due to `perf_event_paranoid`, a virtual machine, or other operating systems) are reported as unavailable. It should be set before measuring, on the thread which measures.
+    pub fn set_measure_perf_events(&mut self, measure_perf_events: bool) {
+        if !measure_perf_events {
+            self.perf_counters = None;
+        } else if self.perf_counters.is_none() {
+            self.perf_counters = Some(PerfCounters::open());
+        }
+    }
+
+    #[inline]
+    /// Check this measurer whether it excludes dropping the returned values from timing.
+    pub fn is_deferring_drop(&self) -> bool {
+        self.defer_drop
+    }
+
+    #[inline]
+    /// Set whether to drop the values returned by measured functions after the timer stops, so that the cost of their destructors (e.g. freeing a big `Vec`) is not included. It can be done for a single measurement by `measure_deferring_drop`.
+    pub fn set_defer_drop(&mut self, defer_drop: bool) {
+        self.defer_drop = defer_drop;
+    }
+
+    #[inline]
+    fn new_result(&self) -> MeasureResult {
+        let mut result = MeasureResult::empty();
+
+        if self.retain_samples {
+            result.samples = Some(Vec::new());
+            result.sample_weights = Some(Vec::new());
+        }
+
+        if self.record_histogram {
+            result.histogram = Some(Histogram::new());
+        }
+
+        if self.measure_cpu_time {
+            result.cpu_times = Some(CpuTimes::zero());
+        }
+
+        if self.perf_counters.is_some() {
+            result.perf_counts = Some(PerfTotals::zero());
+        }
+
+        if allocation_stats().is_some() {
+            result.allocations = Some(AllocationStats::default());
+        }
+
+        result
+    }
+
+    #[inline]
+    fn resolution(&mut self) -> Duration {
+        let clock = self.clock;
+
+        self.clock_info.get_or_insert_with(|| cached_clock_info(clock)).resolution
+    }
+
+    /// Take the readings right before timing. The CPU time is read and the performance counters are enabled last, so that they are next to the clock reading.
+    #[inline]
+    fn probe(&self) -> Probe {
+        let allocations = allocation_stats();
+
+        let cpu_times = if self.measure_cpu_time { Some(CpuTimes::start()) } else { None };
+
+        if let Some(perf_counters) = &self.perf_counters {
+            perf_counters.start();
+        }
+
+        Probe {
+            cpu_times,
+            perf_counts: None,
+            allocations,
+        }
+    }
+
+    /// Take the deltas of the readings of a probe right after timing, in the reverse order.
+    #[inline]
+    fn finish_probe(&self, probe: Probe, elapsed: Duration) -> Probe {
+        let perf_counts = self.perf_counters.as_ref().map(PerfCounters::stop);
+
+        let cpu_times = probe.cpu_times.map(|start| start.finish(elapsed));
+
+        let allocations =
+            probe.allocations.and_then(|earlier| allocation_stats().map(|now| now.since(earlier)));
+
+        Probe {
+            cpu_times,
+            perf_counts,
+            allocations,
+        }
+    }
+
+    #[inline]
+    /// Record `iterations` iterations which took `elapsed` in total, with the deltas of a probe.
+    fn update(
+        &mut self,
+        elapsed: Duration,
+        iterations: u64,
+        throughput: Option<Throughput>,
+        deltas: Probe,
+    ) {
+        let Probe {
+            cpu_times,
+            perf_counts,
+            allocations,
+        } = deltas;
+
+        let resolution = self.resolution();
+
+        let overhead = match self.overhead {
+            Some(overhead) => overhead.min(elapsed),
+            None => Duration::from_secs(0),
+        };
+
+        if self.result.is_none() {
+            self.result = Some(self.new_result());
+        }
+
+        if let Some(result) = &mut self.result {
+            result.push_batch(elapsed - overhead, iterations);
+
+            result.overhead += overhead;
+            if result.clock_info.is_none() {
+                result.clock_info = self.clock_info.clone();
+            }
 
-                result.total_elapsed += elapsed;
+            if elapsed < resolution {
+                result.below_resolution += u128::from(iterations);
+            }
+
+            if let Some(throughput) = throughput {
+                result.push_throughput(throughput, iterations);
+            }
+
+            if let (Some(total), Some(cpu_times)) = (&mut result.cpu_times, cpu_times) {
+                total.add(&cpu_times);
+            }
+
+            if let (Some(total), Some(perf_counts), Some(perf_counters)) =
+                (&mut result.perf_counts, perf_counts, &self.perf_counters)
+            {
+                total.add(&perf_counts, perf_counters.overhead());
+            }
+
+            if let (Some(total), Some(allocations)) = (&mut result.allocations, allocations) {
+                total.add(&allocations);
+            }
+        }
+    }
+
+    #[inline]
+    /// Time a function and probe around it. The returned value is dropped inside the timed region.
+    fn time<M, K>(&self, f: M) -> (Duration, Probe)
+    where
+        M: FnOnce() -> K, {
+        let (elapsed, deltas, _) = self.time_probed(|| {
+            black_box(f());
+        });
+
+        (elapsed, deltas)
+    }
+
+    #[inline]
+    /// Time a function and probe around it, and return its returned value without dropping it.
+    fn time_probed<M, K>(&self, f: M) -> (Duration, Probe, K)
+    where
+        M: FnOnce() -> K, {
+        let probe = self.probe();
+
+        let (elapsed, output) = self.time_output(f);
+
+        (elapsed, self.finish_probe(probe, elapsed), output)
+    }
+
+    #[inline]
+    /// Time a function, and return its returned value without dropping it.
+    fn time_output<M, K>(&self, f: M) -> (Duration, K)
+    where
+        M: FnOnce() -> K, {
+        match self.clock {
+            Some(clock) => {
+                let start = clock.now();
+
+                let output = black_box(f());
+
+                (clock::elapsed(clock, start, clock.now()), output)
             },
             None => {
-                self.result = Some(MeasureResult::new(elapsed));
+                let start = Instant::now();
+
+                let output = black_box(f());
+
+                (start.elapsed(), output)
             },
         }
     }
 
+    #[inline]
+    fn read_clock(&self) -> ClockReading {
+        match self.clock {
+            Some(clock) => ClockReading::Clock(clock.now()),
+            None => ClockReading::Instant(Instant::now()),
+        }
+    }
+
+    #[inline]
+    fn elapsed_since(&self, start: ClockReading) -> Duration {
+        match (self.clock, start) {
+            (Some(clock), ClockReading::Clock(start)) => clock::elapsed(clock, start, clock.now()),
+            (_, ClockReading::Instant(start)) => start.elapsed(),
+            // the clock cannot be changed while a guard borrows the measurer
+            (None, ClockReading::Clock(_)) => unreachable!(),
+        }
+    }
+
+    #[inline]
+    fn measure_once<M, K>(&mut self, throughput: Option<Throughput>, defer_drop: bool, f: M)
+    where
+        M: FnOnce() -> K, {
+        if defer_drop {
+            let (elapsed, deltas, output) = self.time_probed(f);
+
+            self.update(elapsed, 1, throughput, deltas);
+
+            drop(output);
+        } else {
+            let (elapsed, deltas) = self.time(f);
+
+            self.update(elapsed, 1, throughput, deltas);
+        }
+    }
+
     #[inline]
     /// Measure a function by executing it once.
     pub fn measure<M, K>(&mut self, f: M)
     where
         M: FnOnce() -> K, {
-        let start = Instant::now();
+        self.measure_once(self.throughput, self.defer_drop, f);
+    }
+
+    #[inline]
+    /// Measure a function by executing it once, and drop the returned value after the timer stops.
+    pub fn measure_deferring_drop<M, K>(&mut self, f: M)
+    where
+        M: FnOnce() -> K, {
+        self.measure_once(self.throughput, true, f);
+    }
+
+    #[inline]
+    /// Execute a function once without timing it, and measure how long does dropping its returned value take.
+    pub fn measure_drop<M, K>(&mut self, f: M)
+    where
+        M: FnOnce() -> K, {
+        let output = f();
+
+        let (elapsed, deltas) = self.time(move || drop(output));
+
+        self.update(elapsed, 1, self.throughput, deltas);
+    }
+
+    /// Measure a function by executing it `n` times between a single pair of clock readings. Every iteration is regarded as taking the average time, so operations which are much shorter than the resolution or the cost of the clock can be measured. Nothing is measured if `n` is `0`.
+    pub fn measure_iter<M, K>(&mut self, n: u64, mut f: M)
+    where
+        M: FnMut() -> K, {
+        if n == 0 {
+            return;
+        }
+
+        let (elapsed, deltas) = self.time(|| {
+            for _ in 0..n {
+                black_box(f());
+            }
+        });
+
+        self.update(elapsed, n, self.throughput, deltas);
+    }
+
+    /// Measure a function like `measure_iter`, but choose `n` automatically, so that the timed region takes at least `1000` times the clock resolution. `n` is determined at the first call (by doubling it, without recording), and reused by later calls of this measurer.
+    pub fn measure_iter_auto<M, K>(&mut self, mut f: M)
+    where
+        M: FnMut() -> K, {
+        let n = match self.auto_iterations {
+            Some(n) => n,
+            None => {
+                let target = self.resolution() * AUTO_ITERATIONS_RESOLUTION_FACTOR;
+
+                let mut n = 1u64;
+
+                loop {
+                    let (elapsed, _) = self.time_output(|| {
+                        for _ in 0..n {
+                            black_box(f());
+                        }
+                    });
+
+                    if elapsed >= target || n >= 1 << 40 {
+                        break;
+                    }
+
+                    n *= 2;
+                }
+
+                self.auto_iterations = Some(n);
+
+                n
+            },
+        };
+
+        self.measure_iter(n, f);
+    }
+
+    #[inline]
+    /// Get the number of iterations per measurement chosen by `measure_iter_auto`, if it has been called.
+    pub fn get_auto_iterations(&self) -> Option<u64> {
+        self.auto_iterations
+    }
+
+    #[inline]
+    /// Measure a future by driving it to completion with the bundled `BlockingExecutor`. See `measure_async_with`.
+    pub fn measure_async<F: Future>(&mut self, future: F) {
+        self.measure_async_with(&BlockingExecutor, future);
+    }
+
+    /// Measure a future by driving it to completion with an executor. The time from its first poll to its completion is recorded as one iteration, so that setting up and tearing down the executor is not included, and how many times it was polled is counted.
+    pub fn measure_async_with<E: Executor, F: Future>(&mut self, executor: &E, future: F) {
+        let (output, elapsed, deltas, polls) = executor.block_on(TimedFuture {
+            measurer: self,
+            future,
+            probe: None,
+            start: None,
+            polls: 0,
+        });
+
+        let throughput = self.throughput;
+
+        self.update(elapsed, 1, throughput, deltas);
+
+        if let Some(result) = &mut self.result {
+            result.polls += u128::from(polls);
+        }
+
+        drop(black_box(output));
+    }
+
+    #[inline]
+    /// Measure a function which processes a specific amount of data by executing it once.
+    pub fn measure_with_throughput<M, K>(&mut self, throughput: Throughput, f: M)
+    where
+        M: FnOnce() -> K, {
+        self.measure_once(Some(throughput), self.defer_drop, f);
+    }
+
+    #[inline]
+    /// Measure a function which consumes an input by executing it once. The input is prepared by `setup` outside the timed region, so that it does not need to be cloned inside the measured function.
+    pub fn measure_with_setup<S, I, R, K>(&mut self, setup: S, routine: R)
+    where
+        S: FnOnce() -> I,
+        R: FnOnce(I) -> K, {
+        let input = setup();
+
+        self.measure_once(self.throughput, self.defer_drop, || routine(input));
+    }
+
+    /// Measure a function which consumes an input by executing it for a batch of inputs. All inputs of the batch are prepared by `setup` before timing, and all outputs are dropped after timing. Every iteration in the batch is regarded as taking the average time of the batch, which makes short functions measurable, at the cost of keeping the whole batch in memory.
+    pub fn measure_batched<S, I, R, K>(
+        &mut self,
+        mut setup: S,
+        mut routine: R,
+        batch_size: BatchSize,
+    ) where
+        S: FnMut() -> I,
+        R: FnMut(I) -> K, {
+        let iterations = batch_size.iterations();
+
+        let mut inputs: Vec<I> = (0..iterations).map(|_| setup()).collect();
+        let mut outputs: Vec<K> = Vec::with_capacity(inputs.len());
 
-        black_box(f());
+        let (elapsed, deltas) = self.time(|| {
+            for input in inputs.drain(..) {
+                outputs.push(routine(input));
+            }
+        });
 
-        self.update(start.elapsed());
+        self.update(elapsed, iterations, self.throughput, deltas);
+
+        drop(outputs);
+    }
+
+    #[inline]
+    /// Start timing a measurement manually, for code which cannot be wrapped in a single closure. The measurement is recorded when the returned guard is stopped or dropped.
+    pub fn start(&mut self) -> MeasureGuard<'_> {
+        let probe = self.probe();
+
+        let start = self.read_clock();
+
+        MeasureGuard {
+            measurer: self,
+            probe:    Some(probe),
+            start:    Some(start),
+            elapsed:  Duration::from_secs(0),
+        }
+    }
+
+    #[inline]
+    /// Record an iteration which was timed elsewhere (by another clock, a device, a child process, etc.). The timer overhead is not subtracted, and the clock resolution is not checked.
+    pub fn record(&mut self, elapsed: Duration) {
+        self.record_n(1, elapsed);
+    }
+
+    /// Record `count` iterations which were timed elsewhere and took `total` in total. Every iteration is regarded as taking the average time. Nothing is recorded if `count` is `0`.
+    pub fn record_n(&mut self, count: u64, total: Duration) {
+        if count == 0 {
+            return;
+        }
+
+        if self.result.is_none() {
+            self.result = Some(self.new_result());
+        }
+
+        if let Some(result) = &mut self.result {
+            result.push_batch(total, count);
+
+            if let Some(throughput) = self.throughput {
+                result.push_throughput(throughput, count);
+            }
+        }
+    }
+
+    #[inline]
+    /// Take the result of the current run of a benchmarked function. A passed run results in an empty result.
+    pub(crate) fn take_result(&mut self) -> Result<MeasureResult, BenchmarkError> {
+        if self.pass {
+            self.pass = false;
+            self.result = None;
+
+            Ok(MeasureResult::empty())
+        } else {
+            self.result.take().ok_or(BenchmarkError::MeasurerNotMeasured)
+        }
     }
 
     #[inline]
@@ -67,3 +625,95 @@ impl Measurer {
         self.pass = true;
     }
 }
+
+/// A measurement in progress, started by `Measurer::start`. It is recorded as one iteration when it is stopped or dropped.
+#[derive(Debug)]
+pub struct MeasureGuard<'a> {
+    measurer: &'a mut Measurer,
+    probe:    Option<Probe>,
+    /// `None` while paused.
+    start:    Option<ClockReading>,
+    /// The time measured before the last pause.
+    elapsed:  Duration,
+}
+
+impl<'a> MeasureGuard<'a> {
+    #[inline]
+    /// Stop timing until `resume` is called. Only the timing excludes the paused periods; CPU time, performance counters and allocations are still counted.
+    pub fn pause(&mut self) {
+        if let Some(start) = self.start.take() {
+            self.elapsed += self.measurer.elapsed_since(start);
+        }
+    }
+
+    #[inline]
+    /// Continue timing after `pause`.
+    pub fn resume(&mut self) {
+        if self.start.is_none() {
+            self.start = Some(self.measurer.read_clock());
+        }
+    }
+
+    #[inline]
+    /// Check whether the timing is paused.
+    pub fn is_paused(&self) -> bool {
+        self.start.is_none()
+    }
+
+    #[inline]
+    /// Stop timing and record the measurement. It is the same as dropping the guard.
+    pub fn stop(self) {}
+}
+
+impl<'a> Drop for MeasureGuard<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        self.pause();
+
+        if let Some(probe) = self.probe.take() {
+            let deltas = self.measurer.finish_probe(probe, self.elapsed);
+
+            let throughput = self.measurer.throughput;
+
+            self.measurer.update(self.elapsed, 1, throughput, deltas);
+        }
+    }
+}
+
+/// Wraps a future to time it from its first poll to its completion.
+struct TimedFuture<'a, F> {
+    measurer: &'a Measurer,
+    future:   F,
+    probe:    Option<Probe>,
+    start:    Option<ClockReading>,
+    polls:    u64,
+}
+
+impl<'a, F: Future> Future for TimedFuture<'a, F> {
+    type Output = (F::Output, Duration, Probe, u64);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // the inner future is never moved out of the pinned wrapper
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if this.start.is_none() {
+            this.probe = Some(this.measurer.probe());
+            this.start = Some(this.measurer.read_clock());
+        }
+
+        this.polls += 1;
+
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        match future.poll(cx) {
+            Poll::Ready(output) => {
+                let elapsed = this.measurer.elapsed_since(this.start.unwrap());
+
+                let deltas = this.measurer.finish_probe(this.probe.take().unwrap(), elapsed);
+
+                Poll::Ready((output, elapsed, deltas, this.polls))
+            },
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::stats;
+
+/// The method to detect outliers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlierMethod {
+    /// Tukey's fences. Samples beyond `1.5` interquartile ranges from the quartiles are mild outliers, and those beyond `3` interquartile ranges are severe outliers.
+    Tukey,
+    /// Samples beyond `3` scaled median absolute deviations (`1.4826 * MAD`, which estimates the standard deviation) from the median are mild outliers, and those beyond `5` are severe outliers.
+    Mad,
+}
+
+/// The classification of a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlierClass {
+    LowSevere,
+    LowMild,
+    NotOutlier,
+    HighMild,
+    HighSevere,
+}
+
+impl OutlierClass {
+    #[inline]
+    /// Check whether the sample is an outlier (mild or severe).
+    pub fn is_outlier(self) -> bool {
+        self != OutlierClass::NotOutlier
+    }
+
+    #[inline]
+    /// Check whether the sample is a severe outlier.
+    pub fn is_severe(self) -> bool {
+        matches!(self, OutlierClass::LowSevere | OutlierClass::HighSevere)
+    }
+}
+
+/// How many samples are classified as outliers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Outliers {
+    pub low_severe:  usize,
+    pub low_mild:    usize,
+    pub high_mild:   usize,
+    pub high_severe: usize,
+}
+
+impl Outliers {
+    #[inline]
+    /// Get how many samples are mild outliers.
+    pub fn mild(&self) -> usize {
+        self.low_mild + self.high_mild
+    }
+
+    #[inline]
+    /// Get how many samples are severe outliers.
+    pub fn severe(&self) -> usize {
+        self.low_severe + self.high_severe
+    }
+
+    #[inline]
+    /// Get how many samples are outliers. They are the ones excluded by `MeasureResult::elapsed_without_outliers`.
+    pub fn total(&self) -> usize {
+        self.mild() + self.severe()
+    }
+
+    #[inline]
+    pub(crate) fn count(&mut self, class: OutlierClass) {
+        match class {
+            OutlierClass::LowSevere => self.low_severe += 1,
+            OutlierClass::LowMild => self.low_mild += 1,
+            OutlierClass::NotOutlier => (),
+            OutlierClass::HighMild => self.high_mild += 1,
+            OutlierClass::HighSevere => self.high_severe += 1,
+        }
+    }
+}
+
+pub(crate) struct Fences {
+    low_severe:  f64,
+    low_mild:    f64,
+    high_mild:   f64,
+    high_severe: f64,
+}
+
+impl Fences {
+    /// Determine the fences of sorted samples and their weights (how many iterations every sample stands for).
+    pub(crate) fn new(method: OutlierMethod, sorted: &[(f64, u64)]) -> Fences {
+        let (center_low, center_high, spread, mild, severe) = match method {
+            OutlierMethod::Tukey => {
+                let q1 = stats::weighted_percentile_of_sorted(sorted, 0.25);
+                let q3 = stats::weighted_percentile_of_sorted(sorted, 0.75);
+
+                (q1, q3, q3 - q1, 1.5, 3.0)
+            },
+            OutlierMethod::Mad => {
+                let median = stats::weighted_median_of_sorted(sorted);
+
+                (median, median, stats::weighted_mad_of_sorted(sorted) * 1.4826, 3.0, 5.0)
+            },
+        };
+
+        Fences {
+            low_severe:  center_low - spread * severe,
+            low_mild:    center_low - spread * mild,
+            high_mild:   center_high + spread * mild,
+            high_severe: center_high + spread * severe,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn classify(&self, sample: f64) -> OutlierClass {
+        if sample < self.low_severe {
+            OutlierClass::LowSevere
+        } else if sample < self.low_mild {
+            OutlierClass::LowMild
+        } else if sample > self.high_severe {
+            OutlierClass::HighSevere
+        } else if sample > self.high_mild {
+            OutlierClass::HighMild
+        } else {
+            OutlierClass::NotOutlier
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_with_outliers() -> Vec<(f64, u64)> {
+        let mut sorted: Vec<(f64, u64)> = (1..=100).map(|i| (f64::from(i), 1)).collect();
+
+        sorted.extend([(-200.0, 1), (-80.0, 1), (180.0, 1), (300.0, 1)]);
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        sorted
+    }
+
+    #[test]
+    fn tukey_fences() {
+        let fences = Fences::new(OutlierMethod::Tukey, &sorted_with_outliers());
+
+        assert_eq!(OutlierClass::LowSevere, fences.classify(-200.0));
+        assert_eq!(OutlierClass::LowMild, fences.classify(-80.0));
+        assert_eq!(OutlierClass::LowMild, fences.classify(-55.0));
+        assert_eq!(OutlierClass::NotOutlier, fences.classify(50.0));
+        assert_eq!(OutlierClass::HighMild, fences.classify(155.0));
+        assert_eq!(OutlierClass::HighMild, fences.classify(180.0));
+        assert_eq!(OutlierClass::HighSevere, fences.classify(300.0));
+    }
+
+    #[test]
+    fn mad_fences() {
+        let fences = Fences::new(OutlierMethod::Mad, &sorted_with_outliers());
+
+        assert_eq!(OutlierClass::LowSevere, fences.classify(-200.0));
+        assert_eq!(OutlierClass::LowMild, fences.classify(-80.0));
+        assert_eq!(OutlierClass::NotOutlier, fences.classify(-55.0));
+        assert_eq!(OutlierClass::NotOutlier, fences.classify(50.0));
+        assert_eq!(OutlierClass::NotOutlier, fences.classify(155.0));
+        assert_eq!(OutlierClass::HighMild, fences.classify(180.0));
+        assert_eq!(OutlierClass::HighSevere, fences.classify(300.0));
+    }
+}
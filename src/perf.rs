@@ -0,0 +1,374 @@
+/// A performance counter of the Linux kernel (see `perf_event_open(2)`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PerfEvent {
+    /// The CPU time of the task, in nanoseconds (software).
+    TaskClock,
+    /// The number of context switches (software).
+    ContextSwitches,
+    /// The number of times the task was migrated to another CPU (software).
+    CpuMigrations,
+    /// The number of page faults (software).
+    PageFaults,
+    /// The number of CPU cycles (hardware).
+    Cycles,
+    /// The number of retired instructions (hardware).
+    Instructions,
+    /// The number of last-level cache misses (hardware).
+    CacheMisses,
+    /// The number of mispredicted branches (hardware).
+    BranchMisses,
+}
+
+impl PerfEvent {
+    /// All of the events, in the order of their indices.
+    pub const ALL: [PerfEvent; 8] = [
+        PerfEvent::TaskClock,
+        PerfEvent::ContextSwitches,
+        PerfEvent::CpuMigrations,
+        PerfEvent::PageFaults,
+        PerfEvent::Cycles,
+        PerfEvent::Instructions,
+        PerfEvent::CacheMisses,
+        PerfEvent::BranchMisses,
+    ];
+
+    #[inline]
+    /// Check whether this event is counted by the CPU (which may not be exposed, e.g. in virtual machines) rather than by the kernel.
+    pub fn is_hardware(self) -> bool {
+        matches!(
+            self,
+            PerfEvent::Cycles
+                | PerfEvent::Instructions
+                | PerfEvent::CacheMisses
+                | PerfEvent::BranchMisses
+        )
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The counts of events. `None` means the event is unavailable.
+pub(crate) type PerfCounts = [Option<u64>; 8];
+
+/// Accumulate counts. An event stays unavailable if either side does not have it.
+#[inline]
+fn add_perf_counts(counts: &mut PerfCounts, other: &PerfCounts) {
+    for (count, other_count) in counts.iter_mut().zip(other.iter()) {
+        *count = count.and_then(|count| other_count.map(|other_count| count + other_count));
+    }
+}
+
+/// The counts of events accumulated over measurements, and the counts caused by starting and stopping the counters around them. The latter are subtracted from the totals, rather than from every measurement, so that their noise does not accumulate.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PerfTotals {
+    counts:   PerfCounts,
+    overhead: PerfCounts,
+}
+
+impl PerfTotals {
+    #[inline]
+    pub(crate) fn zero() -> PerfTotals {
+        PerfTotals {
+            counts: [Some(0); 8], overhead: [Some(0); 8]
+        }
+    }
+
+    /// Accumulate the counts of a measurement, and the overhead of the counters.
+    #[inline]
+    pub(crate) fn add(&mut self, counts: &PerfCounts, overhead: &PerfCounts) {
+        add_perf_counts(&mut self.counts, counts);
+        add_perf_counts(&mut self.overhead, overhead);
+    }
+
+    #[inline]
+    pub(crate) fn merge(&mut self, other: &PerfTotals) {
+        self.add(&other.counts, &other.overhead);
+    }
+
+    #[inline]
+    pub(crate) fn count(&self, event: PerfEvent) -> Option<u64> {
+        let index = event.index();
+
+        self.counts[index]
+            .and_then(|count| self.overhead[index].map(|overhead| count.saturating_sub(overhead)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::{
+        mem::size_of,
+        os::raw::{c_int, c_ulong, c_void},
+    };
+
+    use super::{PerfCounts, PerfEvent};
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+    const PERF_TYPE_SOFTWARE: u32 = 1;
+
+    const PERF_FLAG_FD_CLOEXEC: c_ulong = 8;
+
+    const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    const PERF_FORMAT_GROUP: u64 = 1 << 3;
+
+    const DISABLED: u64 = 1 << 0;
+    const EXCLUDE_KERNEL: u64 = 1 << 5;
+    const EXCLUDE_HV: u64 = 1 << 6;
+
+    const PERF_EVENT_IOC_ENABLE: c_ulong = 0x2400;
+    const PERF_EVENT_IOC_DISABLE: c_ulong = 0x2401;
+    const PERF_EVENT_IOC_RESET: c_ulong = 0x2403;
+    const PERF_IOC_FLAG_GROUP: c_ulong = 1;
+
+    /// The first published version (`PERF_ATTR_SIZE_VER0`) of `struct perf_event_attr`, which every kernel accepts.
+    #[repr(C)]
+    #[derive(Default)]
+    struct PerfEventAttr {
+        type_:         u32,
+        size:          u32,
+        config:        u64,
+        sample_period: u64,
+        sample_type:   u64,
+        read_format:   u64,
+        flags:         u64,
+        wakeup_events: u32,
+        bp_type:       u32,
+        config1:       u64,
+    }
+
+    fn config_of(event: PerfEvent) -> (u32, u64) {
+        match event {
+            PerfEvent::TaskClock => (PERF_TYPE_SOFTWARE, 1),
+            PerfEvent::PageFaults => (PERF_TYPE_SOFTWARE, 2),
+            PerfEvent::ContextSwitches => (PERF_TYPE_SOFTWARE, 3),
+            PerfEvent::CpuMigrations => (PERF_TYPE_SOFTWARE, 4),
+            PerfEvent::Cycles => (PERF_TYPE_HARDWARE, 0),
+            PerfEvent::Instructions => (PERF_TYPE_HARDWARE, 1),
+            PerfEvent::CacheMisses => (PERF_TYPE_HARDWARE, 3),
+            PerfEvent::BranchMisses => (PERF_TYPE_HARDWARE, 5),
+        }
+    }
+
+    /// Open an event in the group of `leader`, or as a disabled group leader if `leader` is `None`.
+    fn open(event: PerfEvent, leader: Option<c_int>, flags: u64) -> Option<c_int> {
+        let (type_, config) = config_of(event);
+
+        let attr = PerfEventAttr {
+            type_,
+            size: size_of::<PerfEventAttr>() as u32,
+            config,
+            read_format: PERF_FORMAT_GROUP
+                | PERF_FORMAT_TOTAL_TIME_ENABLED
+                | PERF_FORMAT_TOTAL_TIME_RUNNING,
+            flags: if leader.is_none() { flags | DISABLED } else { flags },
+            ..PerfEventAttr::default()
+        };
+
+        // count the calling thread on any CPU
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const PerfEventAttr,
+                0 as libc::pid_t,
+                -1 as c_int,
+                leader.unwrap_or(-1),
+                PERF_FLAG_FD_CLOEXEC,
+            )
+        };
+
+        if fd < 0 {
+            None
+        } else {
+            Some(fd as c_int)
+        }
+    }
+
+    /// How many times the counters are started and stopped around nothing to determine their overhead.
+    const OVERHEAD_SAMPLES: usize = 101;
+
+    /// The counters opened for the current thread, as one group which is enabled and disabled at once.
+    #[derive(Debug)]
+    pub(crate) struct PerfCounters {
+        /// The opened events, in the order of their values in a read of the group. The first one is the group leader.
+        events:   Vec<(PerfEvent, c_int)>,
+        /// The median counts of starting and stopping the counters, such as the task clock of the `ioctl` calls.
+        overhead: PerfCounts,
+    }
+
+    impl PerfCounters {
+        /// Open every available event. If the kernel does not allow counting in kernel mode (`perf_event_paranoid`), only user mode is counted. If the hardware events cannot be scheduled together, only the software events are counted.
+        pub(crate) fn open() -> PerfCounters {
+            let counters = PerfCounters::open_events(PerfEvent::ALL.iter().cloned());
+
+            if counters.overhead.iter().any(Option::is_some) {
+                return counters;
+            }
+
+            drop(counters);
+
+            PerfCounters::open_events(
+                PerfEvent::ALL.iter().cloned().filter(|event| !event.is_hardware()),
+            )
+        }
+
+        fn open_events(events: impl Iterator<Item = PerfEvent>) -> PerfCounters {
+            let mut opened: Vec<(PerfEvent, c_int)> = Vec::new();
+
+            for event in events {
+                let leader = opened.first().map(|(_, fd)| *fd);
+
+                let fd = open(event, leader, 0)
+                    .or_else(|| open(event, leader, EXCLUDE_KERNEL | EXCLUDE_HV));
+
+                if let Some(fd) = fd {
+                    opened.push((event, fd));
+                }
+            }
+
+            let mut counters = PerfCounters {
+                events: opened, overhead: [None; 8]
+            };
+
+            counters.overhead = counters.calibrate_overhead();
+
+            counters
+        }
+
+        fn calibrate_overhead(&self) -> PerfCounts {
+            let samples: Vec<PerfCounts> = (0..OVERHEAD_SAMPLES)
+                .map(|_| {
+                    self.start();
+
+                    self.stop()
+                })
+                .collect();
+
+            let mut overhead = [None; 8];
+
+            for (index, overhead) in overhead.iter_mut().enumerate() {
+                let mut values: Vec<u64> =
+                    samples.iter().filter_map(|counts| counts[index]).collect();
+
+                if values.len() == samples.len() {
+                    values.sort_unstable();
+
+                    *overhead = Some(values[values.len() / 2]);
+                }
+            }
+
+            overhead
+        }
+
+        #[inline]
+        pub(crate) fn overhead(&self) -> &PerfCounts {
+            &self.overhead
+        }
+
+        #[inline]
+        fn ioctl(&self, request: c_ulong) {
+            if let Some((_, leader)) = self.events.first() {
+                unsafe {
+                    libc::ioctl(*leader, request as _, PERF_IOC_FLAG_GROUP);
+                }
+            }
+        }
+
+        /// Reset the counters to zero and enable them. It should be called right before timing.
+        #[inline]
+        pub(crate) fn start(&self) {
+            self.ioctl(PERF_EVENT_IOC_RESET);
+            self.ioctl(PERF_EVENT_IOC_ENABLE);
+        }
+
+        /// Disable the counters and read them. It should be called right after timing. If the group was not counting all the time (because other groups competed for the hardware counters), the counts are scaled up.
+        #[inline]
+        pub(crate) fn stop(&self) -> PerfCounts {
+            self.ioctl(PERF_EVENT_IOC_DISABLE);
+
+            let mut counts = [None; 8];
+
+            let leader = match self.events.first() {
+                Some((_, leader)) => *leader,
+                None => return counts,
+            };
+
+            // the number of events, the enabled time, the running time and the values
+            let mut buffer = [0u64; 3 + 8];
+
+            let size = unsafe {
+                libc::read(leader, buffer.as_mut_ptr() as *mut c_void, size_of::<[u64; 3 + 8]>())
+            };
+
+            let expected = 3 + self.events.len();
+
+            if size != (expected * size_of::<u64>()) as isize
+                || buffer[0] as usize != self.events.len()
+            {
+                return counts;
+            }
+
+            let (enabled, running) = (buffer[1], buffer[2]);
+
+            if running == 0 && enabled > 0 {
+                return counts;
+            }
+
+            for ((event, _), value) in self.events.iter().zip(buffer[3..expected].iter()) {
+                counts[event.index()] = Some(if running < enabled {
+                    (*value as f64 * enabled as f64 / running as f64) as u64
+                } else {
+                    *value
+                });
+            }
+
+            counts
+        }
+    }
+
+    impl Drop for PerfCounters {
+        fn drop(&mut self) {
+            for (_, fd) in self.events.iter() {
+                unsafe {
+                    libc::close(*fd);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use super::PerfCounts;
+
+    /// Performance counters are only available on Linux.
+    #[derive(Debug)]
+    pub(crate) struct PerfCounters;
+
+    impl PerfCounters {
+        #[inline]
+        pub(crate) fn open() -> PerfCounters {
+            PerfCounters
+        }
+
+        #[inline]
+        pub(crate) fn start(&self) {}
+
+        #[inline]
+        pub(crate) fn stop(&self) -> PerfCounts {
+            [None; 8]
+        }
+
+        #[inline]
+        pub(crate) fn overhead(&self) -> &PerfCounts {
+            const UNAVAILABLE: PerfCounts = [None; 8];
+
+            &UNAVAILABLE
+        }
+    }
+}
+
+pub(crate) use sys::PerfCounters;
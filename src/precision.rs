@@ -0,0 +1,66 @@
+use crate::{stats, MeasureResult};
+
+/// The two-sided 95% quantile of the standard normal distribution.
+const NORMAL_QUANTILE_95: f64 = 1.959963984540054;
+
+/// From this many degrees of freedom, the t quantile is approximated by a series instead of being searched for.
+const SERIES_DEGREES_OF_FREEDOM: f64 = 30.0;
+
+/// The two-sided 95% quantile of Student's t-distribution. The precision is checked after every run, so from `30` degrees of freedom it is approximated by the Cornish-Fisher expansion around the normal quantile (with a relative error below `2e-8`), which is much cheaper than searching for it with the incomplete beta function.
+fn t_quantile_95(df: f64) -> f64 {
+    if df < SERIES_DEGREES_OF_FREEDOM {
+        return stats::t_quantile(0.05, df);
+    }
+
+    let z = NORMAL_QUANTILE_95;
+    let z2 = z * z;
+
+    let g1 = (z2 + 1.0) * z / 4.0;
+    let g2 = ((5.0 * z2 + 16.0) * z2 + 3.0) * z / 96.0;
+    let g3 = (((3.0 * z2 + 19.0) * z2 + 17.0) * z2 - 15.0) * z / 384.0;
+    let g4 = ((((79.0 * z2 + 776.0) * z2 + 1482.0) * z2 - 1920.0) * z2 - 945.0) * z / 92160.0;
+
+    z + (g1 + (g2 + (g3 + g4 / df) / df) / df) / df
+}
+
+/// A target of the precision of the mean iteration time, used by `Bench::precision` to decide when to stop. Every measurement is an observation, and a batch of iterations timed together (such as by `Measurer::measure_iter`) is one observation of its average time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+    /// The standard error of the mean divided by the mean, such as `0.01`.
+    RelativeStandardError(f64),
+    /// The half-width of the 95% confidence interval of the mean (the standard error times the quantile of Student's t-distribution) divided by the mean, such as `0.01`.
+    RelativeConfidenceInterval(f64),
+}
+
+impl Precision {
+    /// Check whether a result is precise enough. It needs at least two observations.
+    pub fn is_reached_by(&self, result: &MeasureResult) -> bool {
+        let observations = match &result.running {
+            Some(running) => running.count,
+            None => return false,
+        };
+
+        match result.relative_standard_error() {
+            Some(relative_standard_error) => match *self {
+                Precision::RelativeStandardError(target) => relative_standard_error <= target,
+                Precision::RelativeConfidenceInterval(target) => {
+                    relative_standard_error * t_quantile_95((observations - 1) as f64) <= target
+                },
+            },
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::tests::assert_close;
+
+    #[test]
+    fn t_quantile_95_matches_the_search() {
+        for df in [1.0, 2.0, 10.0, 29.0, 30.0, 31.0, 50.0, 100.0, 1000.0, 1.0e6] {
+            assert_close(stats::t_quantile(0.05, df), t_quantile_95(df), 2e-8);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+/// The resources used during a run, reported by `getrusage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Page faults serviced without any I/O.
+    pub minor_faults:                 u64,
+    /// Page faults which required I/O.
+    pub major_faults:                 u64,
+    /// Context switches because the thread waited for a resource.
+    pub voluntary_context_switches:   u64,
+    /// Context switches because the time slice of the thread ran out or a higher-priority task became runnable.
+    pub involuntary_context_switches: u64,
+    /// The peak resident set size of the process in bytes. It is a high-water mark since the process started, not a difference.
+    pub max_rss:                      u64,
+    /// How many times the file system performed input.
+    pub block_inputs:                 u64,
+    /// How many times the file system performed output.
+    pub block_outputs:                u64,
+}
+
+impl ResourceUsage {
+    /// Accumulate another period. The higher peak resident set size is kept.
+    #[inline]
+    pub(crate) fn add(&mut self, other: &ResourceUsage) {
+        self.minor_faults += other.minor_faults;
+        self.major_faults += other.major_faults;
+        self.voluntary_context_switches += other.voluntary_context_switches;
+        self.involuntary_context_switches += other.involuntary_context_switches;
+        self.max_rss = self.max_rss.max(other.max_rss);
+        self.block_inputs += other.block_inputs;
+        self.block_outputs += other.block_outputs;
+    }
+
+    #[inline]
+    fn since(self, earlier: ResourceUsage) -> ResourceUsage {
+        ResourceUsage {
+            minor_faults:                 self.minor_faults.saturating_sub(earlier.minor_faults),
+            major_faults:                 self.major_faults.saturating_sub(earlier.major_faults),
+            voluntary_context_switches:   self
+                .voluntary_context_switches
+                .saturating_sub(earlier.voluntary_context_switches),
+            involuntary_context_switches: self
+                .involuntary_context_switches
+                .saturating_sub(earlier.involuntary_context_switches),
+            max_rss:                      self.max_rss,
+            block_inputs:                 self.block_inputs.saturating_sub(earlier.block_inputs),
+            block_outputs:                self.block_outputs.saturating_sub(earlier.block_outputs),
+        }
+    }
+}
+
+/// A reading of `getrusage` at the start of a run.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResourceUsageStart {
+    thread_only: bool,
+    usage:       Option<ResourceUsage>,
+}
+
+impl ResourceUsageStart {
+    /// Start a run on the current thread. On Linux, only the current thread is counted; on other Unix-like systems, the whole process is counted.
+    #[inline]
+    pub(crate) fn current() -> ResourceUsageStart {
+        ResourceUsageStart {
+            thread_only: false,
+            usage:       thread_usage().or_else(process_usage),
+        }
+    }
+
+    /// Start a run which is executed by several threads at once. The whole process cannot be counted per thread, so it is only available on Linux.
+    #[inline]
+    pub(crate) fn thread() -> ResourceUsageStart {
+        ResourceUsageStart {
+            thread_only: true, usage: thread_usage()
+        }
+    }
+
+    /// Get the resources used from the start until now.
+    #[inline]
+    pub(crate) fn finish(self) -> Option<ResourceUsage> {
+        let now =
+            if self.thread_only { thread_usage() } else { thread_usage().or_else(process_usage) };
+
+        self.usage.and_then(|earlier| now.map(|now| now.since(earlier)))
+    }
+}
+
+#[cfg(unix)]
+#[inline]
+fn getrusage(who: std::os::raw::c_int) -> Option<ResourceUsage> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::getrusage(who, &mut usage) } != 0 {
+        return None;
+    }
+
+    // `ru_maxrss` is in bytes on Apple platforms and in kilobytes elsewhere
+    let max_rss = if cfg!(any(target_os = "macos", target_os = "ios")) {
+        usage.ru_maxrss as u64
+    } else {
+        usage.ru_maxrss as u64 * 1024
+    };
+
+    Some(ResourceUsage {
+        minor_faults: usage.ru_minflt as u64,
+        major_faults: usage.ru_majflt as u64,
+        voluntary_context_switches: usage.ru_nvcsw as u64,
+        involuntary_context_switches: usage.ru_nivcsw as u64,
+        max_rss,
+        block_inputs: usage.ru_inblock as u64,
+        block_outputs: usage.ru_oublock as u64,
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+fn thread_usage() -> Option<ResourceUsage> {
+    getrusage(libc::RUSAGE_THREAD)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+#[inline]
+fn thread_usage() -> Option<ResourceUsage> {
+    None
+}
+
+#[cfg(unix)]
+#[inline]
+fn process_usage() -> Option<ResourceUsage> {
+    getrusage(libc::RUSAGE_SELF)
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn process_usage() -> Option<ResourceUsage> {
+    None
+}
@@ -0,0 +1,32 @@
+/// A small and fast pseudorandom number generator (SplitMix64), so that resampling can be reproduced by a seed.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    #[inline]
+    pub(crate) fn new(seed: u64) -> Rng {
+        Rng {
+            state: seed
+        }
+    }
+
+    #[inline]
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+
+        let mut z = self.state;
+
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+        z ^ (z >> 31)
+    }
+
+    /// Generate an index in `[0, n)`.
+    #[inline]
+    pub(crate) fn next_index(&mut self, n: usize) -> usize {
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+}
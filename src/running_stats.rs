@@ -0,0 +1,161 @@
+/// The running count, weighted mean, weighted variance (West's variant of Welford's algorithm), min and max of iteration times in nanoseconds. It takes constant memory.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RunningStats {
+    /// The number of observations.
+    pub(crate) count:  u64,
+    /// The sum of the weights of the observations.
+    pub(crate) weight: f64,
+    pub(crate) mean:   f64,
+    /// The weighted sum of squares of differences from the mean.
+    pub(crate) m2:     f64,
+    pub(crate) min:    f64,
+    pub(crate) max:    f64,
+}
+
+impl RunningStats {
+    #[inline]
+    pub(crate) fn new() -> RunningStats {
+        RunningStats {
+            count:  0,
+            weight: 0.0,
+            mean:   0.0,
+            m2:     0.0,
+            min:    f64::INFINITY,
+            max:    0.0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push(&mut self, value: f64) {
+        self.push_weighted(value, 1.0);
+    }
+
+    /// Push an observation which stands for `weight` values, such as the average time of a batch of iterations. It counts as one observation for the variance.
+    #[inline]
+    pub(crate) fn push_weighted(&mut self, value: f64, weight: f64) {
+        self.count += 1;
+        self.weight += weight;
+
+        let delta = value - self.mean;
+
+        self.mean += delta * weight / self.weight;
+        self.m2 += weight * delta * (value - self.mean);
+
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Combine two running states as if all values had been pushed into one (Chan et al.).
+    pub(crate) fn merge(&mut self, other: &RunningStats) {
+        if other.count == 0 {
+            return;
+        }
+
+        if self.count == 0 {
+            *self = *other;
+
+            return;
+        }
+
+        let weight = self.weight + other.weight;
+
+        let delta = other.mean - self.mean;
+
+        self.mean += delta * other.weight / weight;
+        self.m2 += other.m2 + delta * delta * self.weight * other.weight / weight;
+
+        self.count += other.count;
+        self.weight = weight;
+
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// The sample variance (with Bessel's correction for the number of observations). It is the usual sample variance if every weight is `1`.
+    #[inline]
+    pub(crate) fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / self.weight * self.count as f64 / (self.count - 1) as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_pass(values: &[f64]) -> (f64, f64) {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+
+        let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>()
+            / (values.len() - 1) as f64;
+
+        (mean, variance)
+    }
+
+    fn running(values: &[f64]) -> RunningStats {
+        let mut running = RunningStats::new();
+
+        for value in values {
+            running.push(*value);
+        }
+
+        running
+    }
+
+    #[test]
+    fn merge_matches_two_pass_variance() {
+        let values: Vec<f64> =
+            (0..1000u64).map(|i| 1_000_000.0 + ((i * 7919) % 1000) as f64 * 0.37).collect();
+
+        let (mean, variance) = two_pass(&values);
+
+        for split in [1, 2, 333, 500, 999] {
+            let mut merged = running(&values[..split]);
+
+            merged.merge(&running(&values[split..]));
+
+            assert_eq!(values.len() as u64, merged.count);
+            assert!((merged.mean - mean).abs() <= mean * 1e-12, "{}", split);
+            assert!((merged.variance() - variance).abs() <= variance * 1e-9, "{}", split);
+            assert_eq!(1_000_000.0, merged.min);
+            assert_eq!(1_000_000.0 + 999.0 * 0.37, merged.max);
+        }
+    }
+
+    #[test]
+    fn merge_with_empty() {
+        let values = [3.0, 5.0, 10.0];
+
+        let mut merged = RunningStats::new();
+
+        merged.merge(&running(&values));
+        merged.merge(&RunningStats::new());
+
+        let (mean, variance) = two_pass(&values);
+
+        assert_eq!(3, merged.count);
+        assert!((merged.mean - mean).abs() < 1e-12);
+        assert!((merged.variance() - variance).abs() < 1e-12);
+        assert_eq!(3.0, merged.min);
+        assert_eq!(10.0, merged.max);
+    }
+
+    #[test]
+    fn weighted_observations() {
+        let mut running = RunningStats::new();
+
+        // batches of 1, 3 and 6 iterations, which took 10, 60 and 60 in total
+        running.push_weighted(10.0, 1.0);
+        running.push_weighted(20.0, 3.0);
+        running.push_weighted(10.0, 6.0);
+
+        assert_eq!(3, running.count);
+        assert!((running.mean - 13.0).abs() < 1e-12);
+
+        // the weighted sum of squares is 1 * 9 + 3 * 49 + 6 * 9 = 210 over a weight of 10
+        assert!((running.variance() - 21.0 * 1.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,403 @@
+use std::time::Duration;
+
+#[inline]
+pub(crate) fn nanos_to_duration(nanos: f64) -> Duration {
+    if nanos <= 0.0 {
+        Duration::from_secs(0)
+    } else {
+        Duration::from_secs_f64(nanos / 1_000_000_000.0)
+    }
+}
+
+#[inline]
+pub(crate) fn sorted(samples: &[f64]) -> Vec<f64> {
+    let mut samples = samples.to_vec();
+
+    samples.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+    samples
+}
+
+#[inline]
+pub(crate) fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// Linear interpolation between the closest ranks. `p` is in the range `[0, 1]`.
+pub(crate) fn percentile_of_sorted(sorted: &[f64], p: f64) -> f64 {
+    debug_assert!(!sorted.is_empty());
+
+    let rank = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// The median of unsorted samples. The samples are reordered.
+pub(crate) fn median(samples: &mut [f64]) -> f64 {
+    debug_assert!(!samples.is_empty());
+
+    let len = samples.len();
+
+    let (_, upper, _) = samples.select_nth_unstable_by(len / 2, |a, b| a.partial_cmp(b).unwrap());
+    let upper = *upper;
+
+    if len % 2 == 1 {
+        upper
+    } else {
+        let lower = samples[..len / 2].iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        (lower + upper) / 2.0
+    }
+}
+
+/// Sort samples together with their weights (how many iterations every sample stands for).
+pub(crate) fn sorted_weighted(samples: &[f64], weights: &[u64]) -> Vec<(f64, u64)> {
+    let mut sorted: Vec<(f64, u64)> =
+        samples.iter().cloned().zip(weights.iter().cloned()).collect();
+
+    sorted.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    sorted
+}
+
+#[inline]
+fn total_weight(samples: &[(f64, u64)]) -> u64 {
+    samples.iter().map(|(_, weight)| weight).sum()
+}
+
+#[inline]
+pub(crate) fn weighted_mean(samples: &[(f64, u64)]) -> f64 {
+    samples.iter().map(|(value, weight)| value * *weight as f64).sum::<f64>()
+        / total_weight(samples) as f64
+}
+
+/// The value at a rank (from `0`) of sorted weighted samples, as if every sample were repeated by its weight.
+fn value_at_rank(sorted: &[(f64, u64)], rank: u64) -> f64 {
+    let mut accumulated = 0;
+
+    for (value, weight) in sorted {
+        accumulated += weight;
+
+        if accumulated > rank {
+            return *value;
+        }
+    }
+
+    sorted[sorted.len() - 1].0
+}
+
+/// Like `percentile_of_sorted`, as if every sample were repeated by its weight. It is the same as `percentile_of_sorted` if every weight is `1`.
+pub(crate) fn weighted_percentile_of_sorted(sorted: &[(f64, u64)], p: f64) -> f64 {
+    debug_assert!(!sorted.is_empty());
+
+    let rank = p.clamp(0.0, 1.0) * (total_weight(sorted) - 1) as f64;
+
+    let lower = value_at_rank(sorted, rank.floor() as u64);
+    let upper = value_at_rank(sorted, rank.ceil() as u64);
+
+    lower + (upper - lower) * rank.fract()
+}
+
+#[inline]
+pub(crate) fn weighted_median_of_sorted(sorted: &[(f64, u64)]) -> f64 {
+    weighted_percentile_of_sorted(sorted, 0.5)
+}
+
+/// The weighted median absolute deviation, unscaled.
+pub(crate) fn weighted_mad_of_sorted(sorted: &[(f64, u64)]) -> f64 {
+    let median = weighted_median_of_sorted(sorted);
+
+    let mut deviations: Vec<(f64, u64)> =
+        sorted.iter().map(|(value, weight)| ((value - median).abs(), *weight)).collect();
+
+    deviations.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    weighted_median_of_sorted(&deviations)
+}
+
+/// The mean after discarding the `proportion` of the lowest and the highest values, as if every sample were repeated by its weight. It returns `None` if nothing is left.
+pub(crate) fn weighted_trimmed_mean_of_sorted(
+    sorted: &[(f64, u64)],
+    proportion: f64,
+) -> Option<f64> {
+    let total = total_weight(sorted);
+
+    let cut = (total as f64 * proportion) as u64;
+
+    if cut * 2 >= total {
+        return None;
+    }
+
+    let (low, high) = (cut, total - cut);
+
+    let mut accumulated = 0;
+    let mut sum = 0.0;
+
+    for (value, weight) in sorted {
+        let kept = (accumulated + weight).min(high).saturating_sub(accumulated.max(low));
+
+        sum += value * kept as f64;
+
+        accumulated += weight;
+    }
+
+    Some(sum / (high - low) as f64)
+}
+
+/// The natural logarithm of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+
+    let mut series = 1.000000000190015;
+    let mut y = x;
+
+    for coefficient in COEFFICIENTS.iter() {
+        y += 1.0;
+        series += coefficient / y;
+    }
+
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// The continued fraction of the incomplete beta function (modified Lentz's method).
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPSILON: f64 = 3.0e-14;
+    const TINY: f64 = 1.0e-300;
+
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+
+    if d.abs() < TINY {
+        d = TINY;
+    }
+
+    d = 1.0 / d;
+
+    let mut h = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+
+        let delta = d * c;
+
+        h *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    h
+}
+
+/// The regularized incomplete beta function `I_x(a, b)`.
+pub(crate) fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// The complementary error function, with a fractional error less than `1.2e-7`.
+pub(crate) fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// The two-sided p-value of Student's t-distribution with `df` degrees of freedom.
+#[inline]
+pub(crate) fn t_test_p_value(t: f64, df: f64) -> f64 {
+    incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// The critical value of Student's t-distribution with `df` degrees of freedom whose two-sided p-value is `p`, such as about `2.228` for `p = 0.05` and `df = 10`. It is found by bisection.
+pub(crate) fn t_quantile(p: f64, df: f64) -> f64 {
+    let mut low = 0.0;
+    let mut high = 1.0;
+
+    while t_test_p_value(high, df) > p && high < 1.0e6 {
+        low = high;
+        high *= 2.0;
+    }
+
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+
+        if t_test_p_value(middle, df) > p {
+            low = middle;
+        } else {
+            high = middle;
+        }
+
+        if high - low < 1.0e-12 {
+            break;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+/// The two-sided p-value of the standard normal distribution.
+#[inline]
+pub(crate) fn z_test_p_value(z: f64) -> f64 {
+    erfc(z.abs() / std::f64::consts::SQRT_2)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Assert that `actual` is within a relative `tolerance` of `expected`. The reference values in the tests are computed with mpmath.
+    pub(crate) fn assert_close(expected: f64, actual: f64, tolerance: f64) {
+        assert!(
+            (expected - actual).abs() <= expected.abs() * tolerance,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn incomplete_beta_reference() {
+        assert_eq!(0.0, incomplete_beta(2.0, 3.0, 0.0));
+        assert_eq!(1.0, incomplete_beta(2.0, 3.0, 1.0));
+
+        assert_close(0.5, incomplete_beta(4.0, 4.0, 0.5), 1e-9);
+        assert_close(0.3, incomplete_beta(1.0, 1.0, 0.3), 1e-9);
+        assert_close(0.5248, incomplete_beta(2.0, 3.0, 0.4), 1e-9);
+        assert_close(0.934737753831091, incomplete_beta(0.5, 5.0, 0.3), 1e-9);
+        assert_close(0.812186274308855, incomplete_beta(10.0, 2.5, 0.9), 1e-9);
+    }
+
+    #[test]
+    fn erfc_reference() {
+        assert_close(1.0, erfc(0.0), 1.2e-7);
+        assert_close(0.479500122186953, erfc(0.5), 1.2e-7);
+        assert_close(0.157299207050285, erfc(1.0), 1.2e-7);
+        assert_close(0.004677734981047, erfc(2.0), 1.2e-7);
+        assert_close(1.842700792949715, erfc(-1.0), 1.2e-7);
+    }
+
+    #[test]
+    fn t_test_p_value_reference() {
+        assert_close(1.0, t_test_p_value(0.0, 10.0), 1e-9);
+        assert_close(0.5, t_test_p_value(1.0, 1.0), 1e-9);
+        assert_close(0.073388034770740, t_test_p_value(2.0, 10.0), 1e-9);
+        assert_close(0.05, t_test_p_value(2.228138851986274, 10.0), 1e-9);
+        assert_close(0.015392438073302, t_test_p_value(-5.0, 3.0), 1e-9);
+        assert_close(0.002287940053226, t_test_p_value(-3.55, 18.0), 1e-9);
+    }
+
+    #[test]
+    fn weighted_statistics() {
+        let samples = [7.0, 1.0, 3.0, 10.0, 4.0];
+
+        let unit = sorted_weighted(&samples, &[1; 5]);
+        let plain = sorted(&samples);
+
+        for p in [0.0, 0.1, 0.25, 0.5, 0.75, 0.9, 1.0] {
+            assert_eq!(percentile_of_sorted(&plain, p), weighted_percentile_of_sorted(&unit, p));
+        }
+
+        assert_eq!(5.0, weighted_mean(&unit));
+        assert_eq!(Some(14.0 / 3.0), weighted_trimmed_mean_of_sorted(&unit, 0.2));
+        assert_eq!(Some(4.0), weighted_trimmed_mean_of_sorted(&unit, 0.5));
+
+        // the same as 1, 1, 1, 3, 4, 4, 10
+        let weighted = sorted_weighted(&[4.0, 1.0, 10.0, 3.0], &[2, 3, 1, 1]);
+
+        assert_eq!(3.0, weighted_median_of_sorted(&weighted));
+        assert_eq!(2.0, weighted_mad_of_sorted(&weighted));
+        assert_eq!(24.0 / 7.0, weighted_mean(&weighted));
+        assert_eq!(Some(13.0 / 5.0), weighted_trimmed_mean_of_sorted(&weighted, 0.15));
+    }
+
+    #[test]
+    fn t_quantile_reference() {
+        assert_close(12.706204736174707, t_quantile(0.05, 1.0), 1e-9);
+        assert_close(2.228138851986274, t_quantile(0.05, 10.0), 1e-9);
+        assert_close(2.262157162798205, t_quantile(0.05, 9.0), 1e-9);
+        assert_close(3.169272672616951, t_quantile(0.01, 10.0), 1e-9);
+        assert_close(1.959966, t_quantile(0.05, 1.0e6), 1e-6);
+    }
+
+    #[test]
+    fn z_test_p_value_reference() {
+        assert_close(1.0, z_test_p_value(0.0), 1e-7);
+        assert_close(0.317310507862914, z_test_p_value(1.0), 1e-6);
+        assert_close(0.05, z_test_p_value(-1.959963984540054), 1e-6);
+    }
+}